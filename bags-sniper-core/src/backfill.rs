@@ -0,0 +1,280 @@
+//! RPC Backfill for Bags Sniper
+//!
+//! Recovers claims that landed while every Geyser stream was down.
+//! Signatures are fetched with `getSignaturesForAddress` on the Bags
+//! fee-share programs, converted into Geyser updates, and run through
+//! the same `Sniper::process_transaction` path flagged as backfilled.
+
+use crate::multiplexer::{SlotGap, StreamMultiplexer};
+use crate::sniper::{ClaimSource, Sniper};
+use anyhow::{anyhow, Result};
+use log::{error, info, warn};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::{UiInstruction, UiTransactionEncoding, UiTransactionStatusMeta, UiTransactionTokenBalance};
+use tokio::sync::mpsc;
+use yellowstone_grpc_proto::convert_to;
+use yellowstone_grpc_proto::geyser::{SubscribeUpdateTransaction, SubscribeUpdateTransactionInfo};
+use yellowstone_grpc_proto::prelude as proto;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Signatures requested per `getSignaturesForAddress` page
+const PAGE_LIMIT: usize = 1000;
+/// Upper bound on pages walked per program per gap
+const MAX_PAGES: usize = 10;
+
+pub struct Backfiller {
+    rpc_client: Arc<RpcClient>,
+    sniper: Sniper,
+    multiplexer: StreamMultiplexer,
+    program_ids: Vec<Pubkey>,
+}
+
+impl Backfiller {
+    pub fn new(
+        rpc_url: String,
+        sniper: Sniper,
+        multiplexer: StreamMultiplexer,
+        program_ids: &[&str],
+    ) -> Self {
+        Self {
+            rpc_client: Arc::new(RpcClient::new_with_commitment(
+                rpc_url,
+                CommitmentConfig::confirmed(),
+            )),
+            sniper,
+            multiplexer,
+            program_ids: program_ids.iter()
+                .filter_map(|p| Pubkey::from_str(p).ok())
+                .collect(),
+        }
+    }
+
+    /// Backfill every gap reported by the multiplexer, one at a time
+    pub async fn run(self, mut gaps: mpsc::UnboundedReceiver<SlotGap>) {
+        while let Some(gap) = gaps.recv().await {
+            match self.backfill(gap).await {
+                Ok(count) => info!(
+                    "⏪ Backfill of slots {}..{} complete: {} transaction(s) replayed",
+                    gap.from_slot + 1,
+                    gap.to_slot,
+                    count
+                ),
+                Err(e) => error!("❌ Backfill of slots {}..{} failed: {}", gap.from_slot + 1, gap.to_slot, e),
+            }
+        }
+    }
+
+    /// Replay all successful program transactions in the gap, oldest first
+    pub async fn backfill(&self, gap: SlotGap) -> Result<usize> {
+        info!("⏪ Backfilling slots {}..{} over RPC", gap.from_slot + 1, gap.to_slot);
+
+        let mut signatures: Vec<(u64, Signature)> = Vec::new();
+        for program_id in &self.program_ids {
+            signatures.extend(self.signatures_in_gap(program_id, gap).await?);
+        }
+        signatures.sort_by_key(|(slot, _)| *slot);
+        signatures.dedup_by(|a, b| a.1 == b.1);
+
+        let mut replayed = 0;
+        for (_, signature) in signatures {
+            // Skip anything a provider already delivered live
+            if !self.multiplexer.claim_signature(signature.as_ref()) {
+                continue;
+            }
+            match self.fetch_update(&signature).await {
+                Ok((update, block_time)) => {
+                    let age_secs = block_time
+                        .map(|t| (chrono::Utc::now().timestamp() - t).max(0) as u64)
+                        .unwrap_or(0);
+                    self.sniper.process_transaction(update, ClaimSource::Backfill { age_secs }).await;
+                    replayed += 1;
+                }
+                Err(e) => {
+                    warn!("⚠️ Backfill could not fetch {}: {}", signature, e);
+                    self.multiplexer.unclaim_signature(signature.as_ref());
+                }
+            }
+        }
+        Ok(replayed)
+    }
+
+    /// Walk signatures newest to oldest until we pass the start of the gap
+    async fn signatures_in_gap(&self, program_id: &Pubkey, gap: SlotGap) -> Result<Vec<(u64, Signature)>> {
+        let mut found = Vec::new();
+        let mut before: Option<Signature> = None;
+
+        for _ in 0..MAX_PAGES {
+            let page = self.rpc_client.get_signatures_for_address_with_config(
+                program_id,
+                GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    until: None,
+                    limit: Some(PAGE_LIMIT),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                },
+            ).await?;
+
+            let page_len = page.len();
+            let mut reached_start = false;
+            for entry in page {
+                let signature = match Signature::from_str(&entry.signature) {
+                    Ok(s) => s,
+                    Err(_) => continue,
+                };
+                before = Some(signature);
+                if entry.slot <= gap.from_slot {
+                    reached_start = true;
+                    break;
+                }
+                if entry.slot < gap.to_slot && entry.err.is_none() {
+                    found.push((entry.slot, signature));
+                }
+            }
+
+            if reached_start || page_len < PAGE_LIMIT {
+                return Ok(found);
+            }
+        }
+
+        warn!("⚠️ Backfill for {} hit the {} page limit, oldest claims in the gap may be missing", program_id, MAX_PAGES);
+        Ok(found)
+    }
+
+    /// Fetch a transaction and shape it like a Geyser transaction update
+    async fn fetch_update(&self, signature: &Signature) -> Result<(SubscribeUpdateTransaction, Option<i64>)> {
+        let confirmed = self.rpc_client.get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        ).await?;
+
+        let tx = confirmed.transaction.transaction.decode()
+            .ok_or_else(|| anyhow!("Failed to decode transaction"))?;
+        let meta = confirmed.transaction.meta
+            .ok_or_else(|| anyhow!("Transaction has no status meta"))?;
+
+        let update = SubscribeUpdateTransaction {
+            transaction: Some(SubscribeUpdateTransactionInfo {
+                signature: signature.as_ref().to_vec(),
+                is_vote: false,
+                transaction: Some(proto::Transaction {
+                    signatures: tx.signatures.iter().map(|s| s.as_ref().to_vec()).collect(),
+                    message: Some(create_message(&tx.message)),
+                }),
+                meta: Some(create_meta(meta)?),
+                index: 0,
+            }),
+            slot: confirmed.slot,
+        };
+        Ok((update, confirmed.block_time))
+    }
+}
+
+fn create_message(message: &VersionedMessage) -> proto::Message {
+    match message {
+        VersionedMessage::Legacy(m) => proto::Message {
+            header: Some(convert_to::create_header(&m.header)),
+            account_keys: convert_to::create_pubkeys(&m.account_keys),
+            recent_blockhash: m.recent_blockhash.to_bytes().into(),
+            instructions: convert_to::create_instructions(&m.instructions),
+            versioned: false,
+            address_table_lookups: vec![],
+        },
+        VersionedMessage::V0(m) => proto::Message {
+            header: Some(convert_to::create_header(&m.header)),
+            account_keys: convert_to::create_pubkeys(&m.account_keys),
+            recent_blockhash: m.recent_blockhash.to_bytes().into(),
+            instructions: convert_to::create_instructions(&m.instructions),
+            versioned: true,
+            address_table_lookups: convert_to::create_lookups(&m.address_table_lookups),
+        },
+    }
+}
+
+/// Convert the RPC (UI) status meta into the Geyser protobuf shape
+fn create_meta(meta: UiTransactionStatusMeta) -> Result<proto::TransactionStatusMeta> {
+    let inner_instructions: Option<Vec<_>> = meta.inner_instructions.into();
+    let inner_instructions_none = inner_instructions.is_none();
+    let inner_instructions = inner_instructions.unwrap_or_default()
+        .into_iter()
+        .map(|inner| proto::InnerInstructions {
+            index: inner.index as u32,
+            instructions: inner.instructions.into_iter()
+                .filter_map(|ix| match ix {
+                    UiInstruction::Compiled(c) => Some(proto::InnerInstruction {
+                        program_id_index: c.program_id_index as u32,
+                        accounts: c.accounts,
+                        data: bs58::decode(&c.data).into_vec().unwrap_or_default(),
+                        stack_height: c.stack_height,
+                    }),
+                    UiInstruction::Parsed(_) => None,
+                })
+                .collect(),
+        })
+        .collect();
+
+    let log_messages: Option<Vec<String>> = meta.log_messages.into();
+    let pre_token_balances: Option<Vec<_>> = meta.pre_token_balances.into();
+    let post_token_balances: Option<Vec<_>> = meta.post_token_balances.into();
+
+    let (loaded_writable_addresses, loaded_readonly_addresses) = match meta.loaded_addresses {
+        OptionSerializer::Some(loaded) => (
+            decode_pubkeys(&loaded.writable)?,
+            decode_pubkeys(&loaded.readonly)?,
+        ),
+        _ => (vec![], vec![]),
+    };
+
+    Ok(proto::TransactionStatusMeta {
+        err: None,
+        fee: meta.fee,
+        pre_balances: meta.pre_balances,
+        post_balances: meta.post_balances,
+        inner_instructions,
+        inner_instructions_none,
+        log_messages_none: log_messages.is_none(),
+        log_messages: log_messages.unwrap_or_default(),
+        pre_token_balances: create_token_balances(pre_token_balances.unwrap_or_default()),
+        post_token_balances: create_token_balances(post_token_balances.unwrap_or_default()),
+        rewards: vec![],
+        loaded_writable_addresses,
+        loaded_readonly_addresses,
+        return_data: None,
+        return_data_none: true,
+        compute_units_consumed: meta.compute_units_consumed.into(),
+    })
+}
+
+fn create_token_balances(balances: Vec<UiTransactionTokenBalance>) -> Vec<proto::TokenBalance> {
+    balances.into_iter()
+        .map(|b| proto::TokenBalance {
+            account_index: b.account_index as u32,
+            mint: b.mint,
+            ui_token_amount: Some(proto::UiTokenAmount {
+                ui_amount: b.ui_token_amount.ui_amount.unwrap_or_default(),
+                decimals: b.ui_token_amount.decimals as u32,
+                amount: b.ui_token_amount.amount,
+                ui_amount_string: b.ui_token_amount.ui_amount_string,
+            }),
+            owner: Option::<String>::from(b.owner).unwrap_or_default(),
+            program_id: Option::<String>::from(b.program_id).unwrap_or_default(),
+        })
+        .collect()
+}
+
+fn decode_pubkeys(keys: &[String]) -> Result<Vec<Vec<u8>>> {
+    keys.iter()
+        .map(|k| Pubkey::from_str(k).map(|p| p.to_bytes().to_vec()).map_err(|e| anyhow!("Invalid pubkey {}: {}", k, e)))
+        .collect()
+}
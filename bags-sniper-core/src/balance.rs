@@ -0,0 +1,294 @@
+//! Wallet Balance Tracker for Bags Sniper
+//!
+//! Keeps the SOL balance of every trading wallet, seeded over RPC and
+//! kept fresh by a Geyser account subscription. Buys reserve their
+//! amount plus fees before a transaction is built, so concurrent buys
+//! from one wallet are shrunk or skipped instead of failing on-chain.
+
+use crate::geyser::GeyserConnection;
+use crate::multiplexer::{GeyserEndpoint, StreamConfig};
+use futures::StreamExt;
+use log::{error, info, warn};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use yellowstone_grpc_proto::geyser::subscribe_update::UpdateOneof;
+
+/// Base signature fee
+const TX_FEE_LAMPORTS: u64 = 5_000;
+/// Rent for the bought token's ATA plus the temporary wSOL account
+const TOKEN_ACCOUNTS_RENT_LAMPORTS: u64 = 2 * 2_039_280;
+/// Keep the wallet itself rent exempt
+const WALLET_RENT_RESERVE_LAMPORTS: u64 = 890_880;
+/// Buys shrunk below this are skipped instead
+const MIN_BUY_LAMPORTS: u64 = 1_000_000;
+
+const INITIAL_BACKOFF_SECS: u64 = 5;
+const MAX_BACKOFF_SECS: u64 = 60;
+
+/// Lamports a buy needs on top of its amount (the Jito tip is a second transaction fee)
+pub fn buy_overhead_lamports(priority_fee_lamports: u64, tip_lamports: u64) -> u64 {
+    let tip = if tip_lamports > 0 { tip_lamports + TX_FEE_LAMPORTS } else { 0 };
+    TX_FEE_LAMPORTS + priority_fee_lamports + TOKEN_ACCOUNTS_RENT_LAMPORTS + tip
+}
+
+/// Granted reservation; `amount_lamports` may be less than requested
+#[derive(Debug, Clone, Copy)]
+pub struct BalanceReservation {
+    pub id: u64,
+    pub amount_lamports: u64,
+}
+
+#[derive(Debug, Default)]
+struct WalletBalance {
+    /// Last known balance (None until the first RPC or Geyser read)
+    lamports: Option<u64>,
+    slot: u64,
+    /// Reservation id -> (lamports held, slot the buy landed in)
+    /// Landed reservations are dropped once a balance read from that slot or later reflects them
+    reservations: HashMap<u64, (u64, Option<u64>)>,
+}
+
+impl WalletBalance {
+    fn reserved(&self) -> u64 {
+        self.reservations.values().map(|(lamports, _)| lamports).sum()
+    }
+
+    /// Drop the holds of buys the current balance already reflects
+    fn drop_reflected(&mut self) {
+        let slot = self.slot;
+        self.reservations.retain(|_, (_, landed)| !matches!(landed, Some(landed) if slot >= *landed));
+    }
+}
+
+#[derive(Clone)]
+pub struct BalanceTracker {
+    wallets: Arc<Mutex<HashMap<String, WalletBalance>>>,
+    next_id: Arc<Mutex<u64>>,
+    rpc_client: Arc<RpcClient>,
+    /// Wakes the Geyser task when the tracked wallet set changes
+    changed: Arc<Notify>,
+}
+
+impl BalanceTracker {
+    pub fn new(rpc_url: String) -> Self {
+        Self {
+            wallets: Arc::new(Mutex::new(HashMap::new())),
+            next_id: Arc::new(Mutex::new(0)),
+            rpc_client: Arc::new(RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed())),
+            changed: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Start tracking a wallet (no-op if already tracked)
+    pub fn track(&self, wallet: &str) {
+        let added = {
+            let mut wallets = self.wallets.lock().unwrap();
+            if wallets.contains_key(wallet) {
+                false
+            } else {
+                wallets.insert(wallet.to_string(), WalletBalance::default());
+                true
+            }
+        };
+        if added {
+            self.refresh(wallet);
+            self.changed.notify_one();
+        }
+    }
+
+    pub fn untrack(&self, wallet: &str) {
+        if self.wallets.lock().unwrap().remove(wallet).is_some() {
+            self.changed.notify_one();
+        }
+    }
+
+    /// Re-read a wallet's balance over RPC in the background
+    pub fn refresh(&self, wallet: &str) {
+        let tracker = self.clone();
+        let wallet = wallet.to_string();
+        tokio::spawn(async move {
+            let pubkey = match Pubkey::from_str(&wallet) {
+                Ok(pk) => pk,
+                Err(_) => return,
+            };
+            match tracker.rpc_client.get_balance_with_commitment(&pubkey, CommitmentConfig::confirmed()).await {
+                Ok(response) => tracker.set_balance(&wallet, response.value, response.context.slot),
+                Err(e) => warn!("⚠️ Could not fetch balance of {}: {}", wallet, e),
+            }
+        });
+    }
+
+    /// Reserve a buy of `amount_lamports` plus `overhead_lamports`.
+    /// The buy is shrunk to what the wallet can afford, or rejected with
+    /// the reason. Unknown balances are not blocked.
+    pub fn reserve(&self, wallet: &str, amount_lamports: u64, overhead_lamports: u64) -> Result<BalanceReservation, String> {
+        let mut wallets = self.wallets.lock().unwrap();
+        let balance = wallets.entry(wallet.to_string()).or_default();
+
+        let granted = match balance.lamports {
+            None => {
+                warn!("⚠️ Balance of {} not known yet, not checking buy", wallet);
+                amount_lamports
+            }
+            Some(lamports) => {
+                let available = lamports
+                    .saturating_sub(balance.reserved())
+                    .saturating_sub(WALLET_RENT_RESERVE_LAMPORTS)
+                    .saturating_sub(overhead_lamports);
+                if available < amount_lamports.min(MIN_BUY_LAMPORTS) {
+                    return Err(format!(
+                        "insufficient balance: {:.4} SOL free after {:.4} SOL reserved and fees",
+                        available as f64 / 1e9,
+                        balance.reserved() as f64 / 1e9
+                    ));
+                }
+                amount_lamports.min(available)
+            }
+        };
+
+        let id = {
+            let mut next_id = self.next_id.lock().unwrap();
+            *next_id += 1;
+            *next_id
+        };
+        balance.reservations.insert(id, (granted + overhead_lamports, None));
+        Ok(BalanceReservation { id, amount_lamports: granted })
+    }
+
+    /// Free SOL (balance minus holds and the rent reserve), if known
+    pub fn available_sol(&self, wallet: &str) -> Option<f64> {
+        let wallets = self.wallets.lock().unwrap();
+        let balance = wallets.get(wallet)?;
+        let free = balance.lamports?
+            .saturating_sub(balance.reserved())
+            .saturating_sub(WALLET_RENT_RESERVE_LAMPORTS);
+        Some(free as f64 / 1e9)
+    }
+
+    /// Give back a reservation whose buy never landed
+    pub fn release(&self, wallet: &str, id: u64) {
+        if let Some(balance) = self.wallets.lock().unwrap().get_mut(wallet) {
+            balance.reservations.remove(&id);
+        }
+    }
+
+    /// The buy landed in `landed_slot`: keep the hold until a balance read
+    /// from that slot or later reflects it
+    pub fn settle(&self, wallet: &str, id: u64, landed_slot: u64) {
+        if let Some(balance) = self.wallets.lock().unwrap().get_mut(wallet) {
+            if let Some(reservation) = balance.reservations.get_mut(&id) {
+                reservation.1 = Some(landed_slot);
+            }
+            if balance.lamports.is_some() {
+                balance.drop_reflected();
+            }
+        }
+        self.refresh(wallet);
+    }
+
+    fn set_balance(&self, wallet: &str, lamports: u64, slot: u64) {
+        if let Some(balance) = self.wallets.lock().unwrap().get_mut(wallet) {
+            if slot < balance.slot {
+                return;
+            }
+            balance.lamports = Some(lamports);
+            balance.slot = slot;
+            balance.drop_reflected();
+        }
+    }
+
+    fn tracked(&self) -> Vec<String> {
+        self.wallets.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// Follow tracked wallets over a Geyser account subscription, forever
+    pub async fn run(self, endpoint: GeyserEndpoint, config: StreamConfig) {
+        let mut backoff_secs = INITIAL_BACKOFF_SECS;
+
+        loop {
+            let mut geyser = match GeyserConnection::connect(endpoint.url.clone(), endpoint.x_token.clone(), None).await {
+                Ok(g) => g,
+                Err(e) => {
+                    error!("❌ [{}] Balance stream connection failed: {}. Retrying in {}s...", endpoint.name, e, backoff_secs);
+                    tokio::time::sleep(Duration::from_secs(backoff_secs)).await;
+                    backoff_secs = (backoff_secs * 2).min(MAX_BACKOFF_SECS);
+                    continue;
+                }
+            };
+
+            let (requests, mut stream) = match geyser.subscribe_accounts(self.tracked()).await {
+                Ok(s) => s,
+                Err(e) => {
+                    error!("❌ [{}] Balance subscription failed: {}. Retrying in {}s...", endpoint.name, e, backoff_secs);
+                    tokio::time::sleep(Duration::from_secs(backoff_secs)).await;
+                    backoff_secs = (backoff_secs * 2).min(MAX_BACKOFF_SECS);
+                    continue;
+                }
+            };
+            backoff_secs = INITIAL_BACKOFF_SECS;
+
+            // Catch up on anything that changed while disconnected
+            for wallet in self.tracked() {
+                self.refresh(&wallet);
+            }
+
+            let mut ping_timer = tokio::time::interval(config.ping_interval);
+            ping_timer.tick().await;
+            let mut ping_id: i32 = 0;
+
+            loop {
+                tokio::select! {
+                    _ = ping_timer.tick() => {
+                        ping_id = ping_id.wrapping_add(1);
+                        if requests.send(GeyserConnection::ping_request(ping_id)).await.is_err() {
+                            break;
+                        }
+                    }
+                    _ = self.changed.notified() => {
+                        if requests.send(GeyserConnection::accounts_request(self.tracked())).await.is_err() {
+                            break;
+                        }
+                    }
+                    next = stream.next() => {
+                        let update = match next {
+                            Some(Ok(update)) => update,
+                            Some(Err(e)) => {
+                                warn!("[{}] Balance stream error: {}. Reconnecting...", endpoint.name, e);
+                                break;
+                            }
+                            None => {
+                                warn!("[{}] Balance stream closed by server. Reconnecting...", endpoint.name);
+                                break;
+                            }
+                        };
+                        match update.update_oneof {
+                            Some(UpdateOneof::Account(account)) => {
+                                if let Some(info) = account.account {
+                                    if let Ok(pubkey) = Pubkey::try_from(info.pubkey.as_slice()) {
+                                        self.set_balance(&pubkey.to_string(), info.lamports, account.slot);
+                                    }
+                                }
+                            }
+                            Some(UpdateOneof::Ping(_)) => {
+                                ping_id = ping_id.wrapping_add(1);
+                                if requests.send(GeyserConnection::ping_request(ping_id)).await.is_err() {
+                                    break;
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+
+            info!("🔌 [{}] Balance stream reconnecting in {}s...", endpoint.name, backoff_secs);
+            tokio::time::sleep(Duration::from_secs(backoff_secs)).await;
+        }
+    }
+}
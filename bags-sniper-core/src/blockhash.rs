@@ -0,0 +1,227 @@
+//! Blockhash Cache for Bags Sniper
+//!
+//! Keeps the freshest confirmed blockhash and its last valid block height
+//! so signing a buy never waits on `getLatestBlockhash`. Fed by a Geyser
+//! block meta subscription, with RPC polling on a short interval as a
+//! fallback; whichever source has the newer blockhash wins.
+
+use crate::geyser::GeyserConnection;
+use crate::multiplexer::{GeyserEndpoint, StreamConfig};
+use crate::retry::BuyError;
+use futures::StreamExt;
+use log::{error, info, warn};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::clock::MAX_PROCESSING_AGE;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use yellowstone_grpc_proto::geyser::subscribe_update::UpdateOneof;
+
+const INITIAL_BACKOFF_SECS: u64 = 5;
+const MAX_BACKOFF_SECS: u64 = 60;
+/// Fetch over RPC instead of handing out a blockhash older than this
+const DEFAULT_MAX_AGE_MS: u64 = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockhashSource {
+    Geyser,
+    Rpc,
+}
+
+impl BlockhashSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BlockhashSource::Geyser => "geyser",
+            BlockhashSource::Rpc => "rpc",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CachedBlockhash {
+    pub blockhash: Hash,
+    /// Transactions using this blockhash expire past this block height
+    pub last_valid_block_height: u64,
+    pub source: BlockhashSource,
+    pub fetched_at: Instant,
+}
+
+impl CachedBlockhash {
+    /// How stale the blockhash is
+    pub fn age(&self) -> Duration {
+        self.fetched_at.elapsed()
+    }
+}
+
+#[derive(Clone)]
+pub struct BlockhashCache {
+    latest: Arc<RwLock<Option<CachedBlockhash>>>,
+    rpc_client: Arc<RpcClient>,
+    max_age: Duration,
+}
+
+impl BlockhashCache {
+    /// `BLOCKHASH_MAX_AGE_MS` bounds how stale a handed out blockhash may be
+    pub fn new(rpc_url: String) -> Self {
+        let max_age_ms = std::env::var("BLOCKHASH_MAX_AGE_MS").ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_AGE_MS);
+        Self {
+            latest: Arc::new(RwLock::new(None)),
+            rpc_client: Arc::new(RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed())),
+            max_age: Duration::from_millis(max_age_ms),
+        }
+    }
+
+    /// Latest cached blockhash, however stale
+    pub fn latest(&self) -> Option<CachedBlockhash> {
+        *self.latest.read().unwrap()
+    }
+
+    /// Freshest blockhash for signing; falls back to RPC when the cache is
+    /// empty or older than the max age
+    pub async fn get(&self) -> Result<CachedBlockhash, BuyError> {
+        if let Some(cached) = self.latest().filter(|b| b.age() <= self.max_age) {
+            return Ok(cached);
+        }
+        if let Some(stale) = self.latest() {
+            warn!("⚠️ Cached blockhash is {}ms old, fetching over RPC", stale.age().as_millis());
+        }
+        self.refresh_rpc().await.map_err(|e| BuyError::from_client(&e))
+    }
+
+    async fn refresh_rpc(&self) -> Result<CachedBlockhash, solana_client::client_error::ClientError> {
+        let (blockhash, last_valid_block_height) = self.rpc_client
+            .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+            .await?;
+        Ok(self.set(blockhash, last_valid_block_height, BlockhashSource::Rpc))
+    }
+
+    /// Store a blockhash unless the cache already has a newer one; returns the cached value
+    fn set(&self, blockhash: Hash, last_valid_block_height: u64, source: BlockhashSource) -> CachedBlockhash {
+        let mut latest = self.latest.write().unwrap();
+        match *latest {
+            Some(current) if current.last_valid_block_height > last_valid_block_height => current,
+            Some(current) if current.blockhash == blockhash => {
+                let refreshed = CachedBlockhash { fetched_at: Instant::now(), ..current };
+                *latest = Some(refreshed);
+                refreshed
+            }
+            _ => {
+                let fresh = CachedBlockhash {
+                    blockhash,
+                    last_valid_block_height,
+                    source,
+                    fetched_at: Instant::now(),
+                };
+                *latest = Some(fresh);
+                fresh
+            }
+        }
+    }
+
+    /// Poll RPC on a timer, forever
+    pub async fn run_rpc(self, interval: Duration) {
+        let mut timer = tokio::time::interval(interval);
+        loop {
+            timer.tick().await;
+            if let Err(e) = self.refresh_rpc().await {
+                warn!("⚠️ Blockhash refresh failed: {}", e);
+            }
+        }
+    }
+
+    /// Follow confirmed block meta on one Geyser endpoint, forever
+    pub async fn run_geyser(self, endpoint: GeyserEndpoint, config: StreamConfig) {
+        let mut backoff_secs = INITIAL_BACKOFF_SECS;
+
+        loop {
+            let mut geyser = match GeyserConnection::connect(endpoint.url.clone(), endpoint.x_token.clone(), None).await {
+                Ok(g) => g,
+                Err(e) => {
+                    error!("❌ [{}] Blockhash stream connection failed: {}. Retrying in {}s...", endpoint.name, e, backoff_secs);
+                    tokio::time::sleep(Duration::from_secs(backoff_secs)).await;
+                    backoff_secs = (backoff_secs * 2).min(MAX_BACKOFF_SECS);
+                    continue;
+                }
+            };
+
+            let (requests, mut stream) = match geyser.subscribe_blocks_meta().await {
+                Ok(s) => s,
+                Err(e) => {
+                    error!("❌ [{}] Blockhash subscription failed: {}. Retrying in {}s...", endpoint.name, e, backoff_secs);
+                    tokio::time::sleep(Duration::from_secs(backoff_secs)).await;
+                    backoff_secs = (backoff_secs * 2).min(MAX_BACKOFF_SECS);
+                    continue;
+                }
+            };
+            backoff_secs = INITIAL_BACKOFF_SECS;
+
+            let mut ping_timer = tokio::time::interval(config.ping_interval);
+            ping_timer.tick().await;
+            let mut ping_id: i32 = 0;
+
+            loop {
+                tokio::select! {
+                    _ = ping_timer.tick() => {
+                        ping_id = ping_id.wrapping_add(1);
+                        if requests.send(GeyserConnection::ping_request(ping_id)).await.is_err() {
+                            break;
+                        }
+                    }
+                    next = stream.next() => {
+                        let update = match next {
+                            Some(Ok(update)) => update,
+                            Some(Err(e)) => {
+                                warn!("[{}] Blockhash stream error: {}. Reconnecting...", endpoint.name, e);
+                                break;
+                            }
+                            None => {
+                                warn!("[{}] Blockhash stream closed by server. Reconnecting...", endpoint.name);
+                                break;
+                            }
+                        };
+                        match update.update_oneof {
+                            Some(UpdateOneof::BlockMeta(meta)) => {
+                                let height = meta.block_height.map(|h| h.block_height);
+                                match (Hash::from_str(&meta.blockhash), height) {
+                                    // Same validity window getLatestBlockhash reports
+                                    (Ok(blockhash), Some(height)) => {
+                                        self.set(blockhash, height + MAX_PROCESSING_AGE as u64, BlockhashSource::Geyser);
+                                    }
+                                    _ => warn!("[{}] Unusable block meta for slot {}", endpoint.name, meta.slot),
+                                }
+                            }
+                            Some(UpdateOneof::Ping(_)) => {
+                                ping_id = ping_id.wrapping_add(1);
+                                if requests.send(GeyserConnection::ping_request(ping_id)).await.is_err() {
+                                    break;
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+
+            info!("🔌 [{}] Blockhash stream reconnecting in {}s...", endpoint.name, backoff_secs);
+            tokio::time::sleep(Duration::from_secs(backoff_secs)).await;
+        }
+    }
+
+    /// Log the cached blockhash and how stale it is
+    pub fn log_stats(&self) {
+        match self.latest() {
+            Some(b) => info!(
+                "🧱 Blockhash {} | last valid height: {} | source: {} | age: {}ms",
+                b.blockhash,
+                b.last_valid_block_height,
+                b.source.as_str(),
+                b.age().as_millis()
+            ),
+            None => warn!("⚠️ No blockhash cached yet"),
+        }
+    }
+}
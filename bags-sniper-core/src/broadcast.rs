@@ -0,0 +1,314 @@
+//! Fan-out transaction broadcasting for Bags Sniper
+//!
+//! Pushes the same signed transaction to every configured send endpoint
+//! at once: plain RPCs, staked-connection senders and the Jito block
+//! engine. The send returns as soon as one endpoint accepts it. When the
+//! transaction lands, the endpoint that acknowledged it first is credited;
+//! that is the fastest ack, not proof of which path landed it. Only the
+//! initial send is counted, not the tracker's re-broadcasts. Preflight is
+//! skipped everywhere: failures are read from the landed transaction.
+
+use crate::retry::{BuyError, BuyErrorKind};
+use base64::{engine::general_purpose, Engine as _};
+use log::{info, warn};
+use solana_sdk::transaction::VersionedTransaction;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// How long accepted signatures wait to be credited with a landing
+const ACCEPTED_TTL: Duration = Duration::from_secs(120);
+/// Prune the accepted map once it grows past this many entries
+const ACCEPTED_PRUNE_THRESHOLD: usize = 1_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SenderKind {
+    /// Regular JSON-RPC node
+    Rpc,
+    /// Staked-connection sender (JSON-RPC `sendTransaction`, no preflight)
+    Staked,
+    /// Jito block engine transactions endpoint
+    Jito,
+}
+
+impl SenderKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SenderKind::Rpc => "rpc",
+            SenderKind::Staked => "staked",
+            SenderKind::Jito => "jito",
+        }
+    }
+}
+
+impl fmt::Display for SenderKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A single send endpoint
+#[derive(Debug, Clone)]
+pub struct SendEndpoint {
+    pub name: String,
+    pub url: String,
+    pub kind: SenderKind,
+}
+
+impl SendEndpoint {
+    /// The main RPC, plus `SEND_ENDPOINTS` (comma separated, each optionally
+    /// prefixed with its kind: `staked=https://...`, `jito=https://...`)
+    pub fn from_env(rpc_url: &str) -> Vec<SendEndpoint> {
+        let mut endpoints = vec![SendEndpoint::new(SenderKind::Rpc, rpc_url)];
+        let extra = std::env::var("SEND_ENDPOINTS").unwrap_or_default();
+        for entry in extra.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (kind, url) = match entry.split_once('=') {
+                Some(("rpc", url)) => (SenderKind::Rpc, url),
+                Some(("staked", url)) => (SenderKind::Staked, url),
+                Some(("jito", url)) => (SenderKind::Jito, url),
+                _ => (SenderKind::Rpc, entry),
+            };
+            endpoints.push(SendEndpoint::new(kind, url.trim()));
+        }
+        endpoints
+    }
+
+    fn new(kind: SenderKind, url: &str) -> Self {
+        let url = url.trim_end_matches('/').to_string();
+        let without_scheme = url.split("://").last().unwrap_or(&url);
+        let host = without_scheme.split(['/', ':', '?']).next().unwrap_or(without_scheme);
+        Self {
+            name: format!("{}:{}", kind, host),
+            kind,
+            url,
+        }
+    }
+
+    fn request_url(&self) -> String {
+        match self.kind {
+            SenderKind::Jito => format!("{}/api/v1/transactions", self.url),
+            SenderKind::Rpc | SenderKind::Staked => self.url.clone(),
+        }
+    }
+}
+
+/// Per-endpoint send statistics
+#[derive(Debug, Clone)]
+pub struct SenderStats {
+    pub name: String,
+    /// Transactions the endpoint accepted
+    pub accepted: u64,
+    /// Landed transactions this endpoint acknowledged before any other
+    pub first_ack_landed: u64,
+    pub errors: u64,
+    /// Sum of time to accept, over accepted sends
+    pub total_ack: Duration,
+}
+
+impl SenderStats {
+    pub fn avg_ack_ms(&self) -> f64 {
+        if self.accepted == 0 {
+            return 0.0;
+        }
+        self.total_ack.as_secs_f64() * 1000.0 / self.accepted as f64
+    }
+}
+
+struct Accepted {
+    at: Instant,
+    /// Endpoint indexes in order of acceptance
+    endpoints: Vec<usize>,
+}
+
+#[derive(Clone)]
+pub struct Broadcaster {
+    endpoints: Arc<Vec<SendEndpoint>>,
+    client: reqwest::Client,
+    stats: Arc<Mutex<Vec<SenderStats>>>,
+    // Signature -> endpoints that accepted it
+    accepted: Arc<Mutex<HashMap<String, Accepted>>>,
+}
+
+impl Broadcaster {
+    pub fn new(endpoints: Vec<SendEndpoint>) -> Self {
+        let stats = endpoints.iter()
+            .map(|e| SenderStats {
+                name: e.name.clone(),
+                accepted: 0,
+                first_ack_landed: 0,
+                errors: 0,
+                total_ack: Duration::ZERO,
+            })
+            .collect();
+        Self {
+            endpoints: Arc::new(endpoints),
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .expect("Failed to create HTTP client"),
+            stats: Arc::new(Mutex::new(stats)),
+            accepted: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Whether there is more than the main RPC to send to
+    pub fn is_fan_out(&self) -> bool {
+        self.endpoints.len() > 1
+    }
+
+    pub fn endpoint_count(&self) -> usize {
+        self.endpoints.len()
+    }
+
+    /// Send to every endpoint; returns once one accepts, or with the first
+    /// error if none do. Slower endpoints keep going in the background.
+    pub async fn broadcast(&self, tx: &VersionedTransaction) -> Result<String, BuyError> {
+        self.send(tx, true).await
+    }
+
+    /// Send an already broadcast transaction again, without counting it in the stats
+    pub async fn rebroadcast(&self, tx: &VersionedTransaction) -> Result<String, BuyError> {
+        self.send(tx, false).await
+    }
+
+    async fn send(&self, tx: &VersionedTransaction, record: bool) -> Result<String, BuyError> {
+        let signature = tx.signatures[0].to_string();
+        let encoded = bincode::serialize(tx)
+            .map(|bytes| general_purpose::STANDARD.encode(bytes))
+            .map_err(|e| BuyError::new(BuyErrorKind::Other, format!("Failed to serialize tx: {}", e)))?;
+
+        self.prune_accepted();
+        let (result_tx, mut result_rx) = mpsc::unbounded_channel();
+        let started = Instant::now();
+        for index in 0..self.endpoints.len() {
+            let broadcaster = self.clone();
+            let encoded = encoded.clone();
+            let signature = signature.clone();
+            let result_tx = result_tx.clone();
+            tokio::spawn(async move {
+                let result = broadcaster.send_to(index, &encoded).await;
+                if record {
+                    broadcaster.record_send(index, &signature, started.elapsed(), result.is_ok());
+                }
+                if let Err(e) = &result {
+                    warn!("⚠️ Send via {} failed: {}", broadcaster.endpoints[index].name, e);
+                }
+                result_tx.send(result).ok();
+            });
+        }
+        drop(result_tx);
+
+        let mut first_error = None;
+        while let Some(result) = result_rx.recv().await {
+            match result {
+                Ok(()) => return Ok(signature),
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        Err(first_error.unwrap_or_else(|| BuyError::new(BuyErrorKind::Other, "No send endpoints configured")))
+    }
+
+    async fn send_to(&self, index: usize, encoded: &str) -> Result<(), BuyError> {
+        let endpoint = &self.endpoints[index];
+        let body = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "sendTransaction",
+            "params": [encoded, {
+                "encoding": "base64",
+                "skipPreflight": true,
+                "maxRetries": 0
+            }]
+        });
+
+        let res = self.client.post(endpoint.request_url()).json(&body).send().await
+            .map_err(|e| BuyError::new(BuyErrorKind::RpcTimeout, e.to_string()))?;
+        if !res.status().is_success() {
+            let status = res.status();
+            let text = res.text().await.unwrap_or_default();
+            return Err(BuyError::from_rpc_message(&format!("HTTP {}: {}", status, text)));
+        }
+
+        let response: serde_json::Value = res.json().await
+            .map_err(|e| BuyError::new(BuyErrorKind::RpcTimeout, e.to_string()))?;
+        match response.get("error") {
+            Some(error) => {
+                let message = error.to_string();
+                // A resend of a signature that already landed
+                if message.contains("already been processed") {
+                    return Ok(());
+                }
+                Err(BuyError::from_rpc_message(&message))
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn record_send(&self, index: usize, signature: &str, elapsed: Duration, ok: bool) {
+        if let Some(s) = self.stats.lock().unwrap().get_mut(index) {
+            if ok {
+                s.accepted += 1;
+                s.total_ack += elapsed;
+            } else {
+                s.errors += 1;
+            }
+        }
+        if ok {
+            self.accepted.lock().unwrap()
+                .entry(signature.to_string())
+                .or_insert_with(|| Accepted { at: Instant::now(), endpoints: Vec::new() })
+                .endpoints
+                .push(index);
+        }
+    }
+
+    /// Credit the first endpoint to acknowledge a transaction that landed
+    pub fn record_landed(&self, signature: &str) {
+        let first = self.accepted.lock().unwrap()
+            .remove(signature)
+            .and_then(|a| a.endpoints.first().copied());
+        if let Some(index) = first {
+            if let Some(s) = self.stats.lock().unwrap().get_mut(index) {
+                s.first_ack_landed += 1;
+            }
+        }
+    }
+
+    /// Stop tracking a transaction that will not land
+    pub fn forget(&self, signature: &str) {
+        self.accepted.lock().unwrap().remove(signature);
+    }
+
+    fn prune_accepted(&self) {
+        let mut accepted = self.accepted.lock().unwrap();
+        if accepted.len() >= ACCEPTED_PRUNE_THRESHOLD {
+            let now = Instant::now();
+            accepted.retain(|_, a| now.duration_since(a.at) < ACCEPTED_TTL);
+        }
+    }
+
+    pub fn stats(&self) -> Vec<SenderStats> {
+        self.stats.lock().unwrap().clone()
+    }
+
+    /// Log a one-line summary per send endpoint
+    pub fn log_stats(&self) {
+        if !self.is_fan_out() {
+            return;
+        }
+        for s in self.stats() {
+            info!(
+                "📤 Sender {} | accepted: {} | first ack landed: {} | errors: {} | avg ack: {:.1}ms",
+                s.name,
+                s.accepted,
+                s.first_ack_landed,
+                s.errors,
+                s.avg_ack_ms()
+            );
+        }
+    }
+}
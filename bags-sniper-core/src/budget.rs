@@ -0,0 +1,162 @@
+//! Spend limits for Bags Sniper
+//!
+//! Tracks what each user has committed to buys and enforces the
+//! per-token, rolling 24h and lifetime caps from `user_settings`.
+//! Callers check and reserve under one lock, so simultaneous claims
+//! can never together exceed a cap.
+
+use crate::supabase::UserSettings;
+use chrono::{DateTime, Duration, Utc};
+
+/// Caps in SOL; `None` means unlimited
+#[derive(Debug, Clone, Copy)]
+pub struct SpendLimits {
+    pub per_token: Option<f64>,
+    pub daily: Option<f64>,
+    pub total: Option<f64>,
+}
+
+impl From<&UserSettings> for SpendLimits {
+    fn from(settings: &UserSettings) -> Self {
+        Self {
+            per_token: settings.max_buy_per_token,
+            daily: settings.max_daily_spend,
+            total: settings.max_total_spend,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Spend {
+    id: u64,
+    mint: String,
+    amount_sol: f64,
+    at: DateTime<Utc>,
+}
+
+/// Buys committed by one user: sent, in flight, or restored from trade_logs
+#[derive(Debug, Default)]
+pub struct SpendLedger {
+    spends: Vec<Spend>,
+    next_id: u64,
+}
+
+impl SpendLedger {
+    /// Record a buy that already happened (seeding from history)
+    pub fn record(&mut self, mint: &str, amount_sol: f64, at: DateTime<Utc>) {
+        self.push(mint, amount_sol, at);
+    }
+
+    /// Check every cap and reserve `amount_sol` for `mint`.
+    /// Returns the reservation id, or the rejection reason.
+    pub fn reserve(&mut self, limits: &SpendLimits, mint: &str, amount_sol: f64) -> Result<u64, String> {
+        let now = Utc::now();
+        let day_ago = now - Duration::hours(24);
+
+        let on_token: f64 = self.spends.iter().filter(|s| s.mint == mint).map(|s| s.amount_sol).sum();
+        let last_day: f64 = self.spends.iter().filter(|s| s.at > day_ago).map(|s| s.amount_sol).sum();
+        let total: f64 = self.spends.iter().map(|s| s.amount_sol).sum();
+
+        let checks = [
+            ("per-token", limits.per_token, on_token),
+            ("24h", limits.daily, last_day),
+            ("total", limits.total, total),
+        ];
+        for (name, cap, spent) in checks {
+            if let Some(cap) = cap {
+                if spent + amount_sol > cap + f64::EPSILON {
+                    return Err(format!(
+                        "{} cap of {} SOL reached ({} SOL spent, buy of {} SOL)",
+                        name, cap, spent, amount_sol
+                    ));
+                }
+            }
+        }
+
+        Ok(self.push(mint, amount_sol, now))
+    }
+
+    /// Give back a reservation whose buy failed
+    pub fn release(&mut self, id: u64) {
+        self.spends.retain(|s| s.id != id);
+    }
+
+    fn push(&mut self, mint: &str, amount_sol: f64, at: DateTime<Utc>) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.spends.push(Spend { id, mint: mint.to_string(), amount_sol, at });
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINT_A: &str = "MintA";
+    const MINT_B: &str = "MintB";
+
+    fn limits(per_token: Option<f64>, daily: Option<f64>, total: Option<f64>) -> SpendLimits {
+        SpendLimits { per_token, daily, total }
+    }
+
+    #[test]
+    fn unlimited_always_reserves() {
+        let mut ledger = SpendLedger::default();
+        let unlimited = limits(None, None, None);
+        for _ in 0..100 {
+            assert!(ledger.reserve(&unlimited, MINT_A, 10.0).is_ok());
+        }
+    }
+
+    #[test]
+    fn per_token_cap_is_per_mint() {
+        let mut ledger = SpendLedger::default();
+        let caps = limits(Some(1.0), None, None);
+        assert!(ledger.reserve(&caps, MINT_A, 0.6).is_ok());
+        assert!(ledger.reserve(&caps, MINT_A, 0.5).unwrap_err().contains("per-token"));
+        assert!(ledger.reserve(&caps, MINT_B, 1.0).is_ok());
+    }
+
+    #[test]
+    fn buy_exactly_at_the_cap_is_allowed() {
+        let mut ledger = SpendLedger::default();
+        let caps = limits(None, None, Some(0.3));
+        assert!(ledger.reserve(&caps, MINT_A, 0.1).is_ok());
+        assert!(ledger.reserve(&caps, MINT_A, 0.1).is_ok());
+        assert!(ledger.reserve(&caps, MINT_B, 0.1).is_ok());
+        assert!(ledger.reserve(&caps, MINT_B, 0.000_001).is_err());
+    }
+
+    #[test]
+    fn back_to_back_reservations_never_overshoot() {
+        // Simultaneous claims reserve one after another under the same lock
+        let mut ledger = SpendLedger::default();
+        let caps = limits(None, Some(1.0), None);
+        let granted = (0..10)
+            .filter(|_| ledger.reserve(&caps, MINT_A, 0.3).is_ok())
+            .count();
+        assert_eq!(granted, 3);
+    }
+
+    #[test]
+    fn daily_cap_ignores_spends_older_than_24h() {
+        let mut ledger = SpendLedger::default();
+        ledger.record(MINT_A, 5.0, Utc::now() - Duration::hours(25));
+        let caps = limits(None, Some(1.0), Some(6.0));
+        assert!(ledger.reserve(&caps, MINT_A, 1.0).is_ok());
+        // The lifetime cap still counts them
+        let caps = limits(None, None, Some(6.0));
+        assert!(ledger.reserve(&caps, MINT_B, 0.5).unwrap_err().contains("total"));
+    }
+
+    #[test]
+    fn released_reservation_frees_the_budget() {
+        let mut ledger = SpendLedger::default();
+        let caps = limits(Some(1.0), None, None);
+        let id = ledger.reserve(&caps, MINT_A, 1.0).unwrap();
+        assert!(ledger.reserve(&caps, MINT_A, 0.5).is_err());
+        ledger.release(id);
+        assert!(ledger.reserve(&caps, MINT_A, 0.5).is_ok());
+    }
+}
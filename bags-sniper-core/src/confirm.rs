@@ -0,0 +1,219 @@
+//! Transaction Confirmation Tracking for Bags Sniper
+//!
+//! Buys are sent without waiting for confirmation. The tracker follows
+//! every sent signature with batched RPC status polls, re-broadcasts it
+//! while it is unseen, and publishes each step (processed, confirmed,
+//! finalized) or the outcome (failed, or expired once its blockhash is no
+//! longer valid) to whoever is watching.
+
+use crate::broadcast::Broadcaster;
+use anyhow::Result;
+use log::warn;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::{TransactionError, VersionedTransaction};
+use solana_transaction_status::TransactionConfirmationStatus;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(400);
+/// Send an unseen transaction again this often until its blockhash expires
+const REBROADCAST_INTERVAL: Duration = Duration::from_secs(2);
+/// Most signatures `getSignatureStatuses` accepts at once
+const MAX_STATUS_BATCH: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxStatus {
+    /// Sent, not seen on chain yet
+    Pending,
+    /// Landed in `slot`, at each commitment level
+    Processed { slot: u64 },
+    Confirmed { slot: u64 },
+    Finalized { slot: u64 },
+    /// Landed with an error
+    Failed(TransactionError),
+    /// Blockhash expired before the transaction landed
+    Expired,
+}
+
+impl TxStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TxStatus::Pending => "pending",
+            TxStatus::Processed { .. } => "processed",
+            TxStatus::Confirmed { .. } => "confirmed",
+            TxStatus::Finalized { .. } => "finalized",
+            TxStatus::Failed(_) => "failed",
+            TxStatus::Expired => "expired",
+        }
+    }
+
+    /// Nothing more will change
+    pub fn is_final(&self) -> bool {
+        matches!(self, TxStatus::Finalized { .. } | TxStatus::Failed(_) | TxStatus::Expired)
+    }
+}
+
+impl fmt::Display for TxStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TxStatus::Failed(e) => write!(f, "failed ({})", e),
+            _ => f.write_str(self.as_str()),
+        }
+    }
+}
+
+struct Tracked {
+    tx: VersionedTransaction,
+    status: watch::Sender<TxStatus>,
+    last_broadcast: Instant,
+    /// Send again while unseen; off for swaps that must not leave the bundle path
+    rebroadcast: bool,
+}
+
+#[derive(Clone)]
+pub struct ConfirmationTracker {
+    tracked: Arc<Mutex<HashMap<Signature, Tracked>>>,
+    rpc_client: Arc<RpcClient>,
+    broadcaster: Broadcaster,
+}
+
+impl ConfirmationTracker {
+    pub fn new(rpc_url: String, broadcaster: Broadcaster) -> Self {
+        Self {
+            tracked: Arc::new(Mutex::new(HashMap::new())),
+            rpc_client: Arc::new(RpcClient::new_with_commitment(rpc_url, CommitmentConfig::processed())),
+            broadcaster,
+        }
+    }
+
+    /// Follow a sent transaction; tracking the same signature again
+    /// subscribes to the existing entry
+    pub fn track(&self, tx: &VersionedTransaction) -> watch::Receiver<TxStatus> {
+        self.insert(tx, true)
+    }
+
+    /// Follow a transaction without ever re-broadcasting it
+    pub fn watch(&self, tx: &VersionedTransaction) -> watch::Receiver<TxStatus> {
+        self.insert(tx, false)
+    }
+
+    fn insert(&self, tx: &VersionedTransaction, rebroadcast: bool) -> watch::Receiver<TxStatus> {
+        let mut tracked = self.tracked.lock().unwrap();
+        match tracked.entry(tx.signatures[0]) {
+            Entry::Occupied(entry) => entry.get().status.subscribe(),
+            Entry::Vacant(entry) => {
+                let (status, rx) = watch::channel(TxStatus::Pending);
+                entry.insert(Tracked {
+                    tx: tx.clone(),
+                    status,
+                    last_broadcast: Instant::now(),
+                    rebroadcast,
+                });
+                rx
+            }
+        }
+    }
+
+    /// Poll every tracked signature, forever
+    pub async fn run(self) {
+        let mut timer = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            timer.tick().await;
+            if let Err(e) = self.poll().await {
+                warn!("⚠️ Confirmation poll failed: {}", e);
+            }
+        }
+    }
+
+    async fn poll(&self) -> Result<()> {
+        let pending: Vec<(Signature, Hash)> = self.tracked.lock().unwrap()
+            .iter()
+            .map(|(signature, t)| (*signature, *t.tx.message.recent_blockhash()))
+            .collect();
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let mut updates = Vec::with_capacity(pending.len());
+        for chunk in pending.chunks(MAX_STATUS_BATCH) {
+            let signatures: Vec<Signature> = chunk.iter().map(|(s, _)| *s).collect();
+            let statuses = self.rpc_client.get_signature_statuses(&signatures).await?.value;
+            for ((signature, blockhash), status) in chunk.iter().zip(statuses) {
+                let next = match status {
+                    Some(s) => match (s.err, s.confirmation_status) {
+                        (Some(e), _) => TxStatus::Failed(e),
+                        (None, Some(TransactionConfirmationStatus::Finalized)) => TxStatus::Finalized { slot: s.slot },
+                        (None, Some(TransactionConfirmationStatus::Confirmed)) => TxStatus::Confirmed { slot: s.slot },
+                        (None, _) => TxStatus::Processed { slot: s.slot },
+                    },
+                    None => TxStatus::Pending,
+                };
+                updates.push((*signature, *blockhash, next));
+            }
+        }
+
+        // Unseen transactions expire with their blockhash
+        let mut valid: HashMap<Hash, bool> = HashMap::new();
+        for (_, blockhash, next) in updates.iter_mut() {
+            if *next != TxStatus::Pending {
+                continue;
+            }
+            let is_valid = match valid.get(blockhash) {
+                Some(v) => *v,
+                None => {
+                    let v = self.rpc_client.is_blockhash_valid(blockhash, CommitmentConfig::processed()).await
+                        .unwrap_or(true);
+                    valid.insert(*blockhash, v);
+                    v
+                }
+            };
+            if !is_valid {
+                *next = TxStatus::Expired;
+            }
+        }
+
+        let mut rebroadcast = Vec::new();
+        {
+            let mut tracked = self.tracked.lock().unwrap();
+            for (signature, _, next) in updates {
+                let entry = match tracked.get_mut(&signature) {
+                    Some(e) => e,
+                    None => continue,
+                };
+                let previous = entry.status.borrow().clone();
+                if previous != next {
+                    entry.status.send_replace(next.clone());
+                }
+                if previous == TxStatus::Pending && next != TxStatus::Pending && next != TxStatus::Expired {
+                    self.broadcaster.record_landed(&signature.to_string());
+                }
+                if next.is_final() {
+                    if next == TxStatus::Expired {
+                        self.broadcaster.forget(&signature.to_string());
+                    }
+                    tracked.remove(&signature);
+                    continue;
+                }
+                if next == TxStatus::Pending && entry.rebroadcast && entry.last_broadcast.elapsed() >= REBROADCAST_INTERVAL {
+                    entry.last_broadcast = Instant::now();
+                    rebroadcast.push(entry.tx.clone());
+                }
+            }
+        }
+
+        for tx in rebroadcast {
+            let broadcaster = self.broadcaster.clone();
+            tokio::spawn(async move {
+                broadcaster.rebroadcast(&tx).await.ok();
+            });
+        }
+        Ok(())
+    }
+}
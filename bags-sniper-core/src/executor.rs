@@ -1,97 +1,753 @@
 //! Transaction Executor for Bags Sniper
 //!
-//! Handles building and signing transactions via Jupiter and local Keypair
+//! Handles building and signing transactions via Jupiter and local Keypair.
+//! Buys with a bribe go to the Jito block engine as a bundle with a tip
+//! transaction; everything else is sent without preflight to every
+//! configured send endpoint. A buy returns its signature as soon as the
+//! first attempt is accepted; retries carry on in the background until it
+//! is confirmed, driven by what the confirmation tracker reports.
 
+use crate::blockhash::BlockhashCache;
+use crate::broadcast::Broadcaster;
+use crate::confirm::{ConfirmationTracker, TxStatus};
 use crate::jupiter::JupiterClient;
+use crate::retry::{BuyError, BuyErrorKind, RetryAction};
 use anyhow::{anyhow, Result};
-use log::info;
-use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::commitment_config::CommitmentConfig;
+use log::{info, warn};
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::{Keypair, Signer};
-use solana_sdk::transaction::VersionedTransaction;
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::{Transaction, VersionedTransaction};
+use std::collections::HashMap;
+use std::future::Future;
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use base64::{Engine as _, engine::general_purpose};
+use tokio::sync::{oneshot, watch};
+
+/// ComputeBudget `SetComputeUnitPrice` instruction tag
+const SET_COMPUTE_UNIT_PRICE: u8 = 3;
+
+const DEFAULT_JITO_BLOCK_ENGINE_URL: &str = "https://mainnet.block-engine.jito.wtf";
+/// Jito tip accounts; one is picked per bundle to spread write locks
+const JITO_TIP_ACCOUNTS: [&str; 8] = [
+    "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5",
+    "HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe",
+    "Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY",
+    "ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49",
+    "DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh",
+    "ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt",
+    "DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL",
+    "3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT",
+];
+const BUNDLE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// A bundle lands within a few slots or not at all; giving up well inside
+/// the blockhash lifetime leaves the RPC fallback time to land
+const DEFAULT_BUNDLE_TIMEOUT_SECS: u64 = 5;
+
+/// Parameters of a single buy
+#[derive(Debug, Clone)]
+pub struct BuyOrder {
+    pub token_mint: String,
+    pub amount_sol: f64,
+    pub slippage_bps: u64,
+    pub priority_fee_lamports: u64,
+    /// No new attempt starts once this much time has passed
+    pub deadline: Duration,
+    /// Jito tip (bribe); 0 sends over RPC
+    pub tip_lamports: u64,
+}
+
+/// Progress of a buy, reported once per step of every attempt
+#[derive(Debug, Clone)]
+pub enum AttemptEvent {
+    /// Attempt is being built; must be recorded durably, an error aborts the buy
+    Started { attempt: u32, amount_sol: f64 },
+    /// Signature of the attempt, reported just before it is sent
+    Sent { attempt: u32, signature: String },
+    /// The attempt was accepted; its status follows it until it settles
+    Tracking { signature: String, status: watch::Receiver<TxStatus> },
+    /// The last started attempt failed; another may follow
+    Failed { signature: Option<String>, error: BuyError },
+}
+
+/// A buy whose first attempt has been accepted
+pub struct SentBuy {
+    /// Signature of the first attempt
+    pub signature: String,
+    /// The confirmed attempt (possibly a later one), or why the buy failed
+    pub outcome: oneshot::Receiver<Result<ConfirmedBuy>>,
+}
+
+/// The attempt that landed
+#[derive(Debug, Clone)]
+pub struct ConfirmedBuy {
+    pub signature: String,
+    /// Slot it landed in (0 for paper trades)
+    pub slot: u64,
+}
+
+/// How an accepted attempt is followed until it settles
+enum Submitted {
+    /// Sent over RPC and followed by the confirmation tracker
+    Tracked(watch::Receiver<TxStatus>),
+    /// Accepted by the Jito block engine under this bundle id
+    Bundle(String),
+}
+
+/// Public address of a base58 private key, None if the key is malformed
+pub fn wallet_address(private_key: &str) -> Option<String> {
+    let bytes = bs58::decode(private_key).into_vec().ok()?;
+    Keypair::from_bytes(&bytes).ok().map(|k| k.pubkey().to_string())
+}
+
+/// Jito block engine settings
+#[derive(Debug, Clone)]
+pub struct JitoConfig {
+    pub block_engine_url: String,
+    /// Send the swap over RPC if the bundle is rejected or doesn't land
+    pub fallback_to_rpc: bool,
+    /// Give up polling a bundle after this long
+    pub bundle_timeout: Duration,
+}
+
+impl JitoConfig {
+    /// Read `JITO_BLOCK_ENGINE_URL`, `JITO_FALLBACK_TO_RPC` and `JITO_BUNDLE_TIMEOUT_SECS`.
+    /// Set `JITO_ENABLED=false` to send every buy over RPC.
+    pub fn from_env() -> Option<Self> {
+        if std::env::var("JITO_ENABLED").map(|v| v == "false").unwrap_or(false) {
+            return None;
+        }
+        Some(Self {
+            block_engine_url: std::env::var("JITO_BLOCK_ENGINE_URL")
+                .unwrap_or_else(|_| DEFAULT_JITO_BLOCK_ENGINE_URL.to_string())
+                .trim_end_matches('/')
+                .to_string(),
+            fallback_to_rpc: std::env::var("JITO_FALLBACK_TO_RPC").map(|v| v != "false").unwrap_or(true),
+            bundle_timeout: Duration::from_secs(
+                std::env::var("JITO_BUNDLE_TIMEOUT_SECS").ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(DEFAULT_BUNDLE_TIMEOUT_SECS),
+            ),
+        })
+    }
+}
+
+/// JSON-RPC client for the Jito block engine bundle API
+#[derive(Clone)]
+pub struct JitoClient {
+    client: reqwest::Client,
+    config: JitoConfig,
+}
+
+impl JitoClient {
+    pub fn new(config: JitoConfig) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .expect("Failed to create HTTP client"),
+            config,
+        }
+    }
+
+    async fn call(&self, method: &str, params: serde_json::Value) -> Result<serde_json::Value> {
+        let url = format!("{}/api/v1/bundles", self.config.block_engine_url);
+        let body = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params
+        });
+
+        let res = self.client.post(&url).json(&body).send().await?;
+        if !res.status().is_success() {
+            let status = res.status();
+            let text = res.text().await.unwrap_or_default();
+            return Err(anyhow!("Jito {} error {}: {}", method, status, text));
+        }
+
+        let mut response: serde_json::Value = res.json().await?;
+        if let Some(error) = response.get("error") {
+            return Err(anyhow!("Jito {} error: {}", method, error));
+        }
+        Ok(response["result"].take())
+    }
+
+    /// Submit base64 transactions as one bundle, returning the bundle id
+    pub async fn send_bundle(&self, transactions: &[VersionedTransaction]) -> Result<String> {
+        let encoded = transactions.iter()
+            .map(|tx| bincode::serialize(tx).map(|bytes| general_purpose::STANDARD.encode(bytes)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let result = self.call("sendBundle", serde_json::json!([encoded, { "encoding": "base64" }])).await?;
+        result.as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("sendBundle returned no bundle id"))
+    }
+
+    /// Status of an in-flight bundle: Invalid, Pending, Failed or Landed
+    pub async fn bundle_status(&self, bundle_id: &str) -> Result<String> {
+        let result = self.call("getInflightBundleStatuses", serde_json::json!([[bundle_id]])).await?;
+        result["value"][0]["status"].as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("No status for bundle {}", bundle_id))
+    }
+
+    /// Poll until the bundle lands, fails, or the bundle timeout (capped
+    /// at `limit`) passes
+    pub async fn wait_for_bundle(&self, bundle_id: &str, limit: Duration) -> Result<()> {
+        let timeout = self.config.bundle_timeout.min(limit);
+        let started = Instant::now();
+        loop {
+            tokio::time::sleep(BUNDLE_POLL_INTERVAL).await;
+            match self.bundle_status(bundle_id).await {
+                Ok(status) => match status.as_str() {
+                    "Landed" => return Ok(()),
+                    "Failed" => return Err(anyhow!("Bundle {} failed", bundle_id)),
+                    // Invalid also covers ids the engine hasn't indexed yet
+                    _ => {}
+                },
+                Err(e) => warn!("⚠️ Could not poll bundle {}: {}", bundle_id, e),
+            }
+            if started.elapsed() >= timeout {
+                return Err(anyhow!("Bundle {} not landed after {}ms", bundle_id, timeout.as_millis()));
+            }
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct TransactionExecutor {
-    pub rpc_client: Arc<RpcClient>,
     pub paper_trading: bool,
+    pub jito: Option<JitoClient>,
+    pub broadcaster: Broadcaster,
+    pub tracker: ConfirmationTracker,
+    pub blockhash: BlockhashCache,
 }
 
 impl TransactionExecutor {
-    pub fn new(rpc_url: String, paper_trading: bool) -> Self {
-        let rpc_client = Arc::new(RpcClient::new_with_commitment(
-            rpc_url,
-            CommitmentConfig::confirmed(),
-        ));
-        
+    pub fn new(
+        paper_trading: bool,
+        broadcaster: Broadcaster,
+        tracker: ConfirmationTracker,
+        blockhash: BlockhashCache,
+    ) -> Self {
         Self {
-            rpc_client,
             paper_trading,
+            jito: JitoConfig::from_env().map(JitoClient::new),
+            broadcaster,
+            tracker,
+            blockhash,
         }
     }
 
-    /// Execute a buy transaction (High-speed path)
-    pub async fn buy_token(
+    /// Execute a buy transaction (High-speed path), retrying by error class
+    ///
+    /// Every attempt is reported through `report`: its `Started` event
+    /// runs alongside building the transaction, and the transaction is
+    /// only sent once it has been recorded. Returns with the signature as
+    /// soon as the first attempt is accepted; the buy carries on in the
+    /// background and settles through `SentBuy::outcome` once confirmed.
+    pub async fn buy_token<R, Fut>(
+        &self,
+        private_key: String,
+        order: BuyOrder,
+        jupiter: Arc<JupiterClient>,
+        report: R,
+    ) -> Result<SentBuy>
+    where
+        R: Fn(AttemptEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let (sent_tx, sent_rx) = oneshot::channel();
+        let (outcome_tx, outcome) = oneshot::channel();
+        let mint = order.token_mint.clone();
+        let executor = self.clone();
+        tokio::spawn(async move {
+            let result = executor.run_buy(&private_key, &order, &jupiter, &report, sent_tx).await;
+            outcome_tx.send(result).ok();
+        });
+
+        match sent_rx.await {
+            Ok(signature) => Ok(SentBuy { signature, outcome }),
+            // Gave up before any attempt was accepted
+            Err(_) => match outcome.await {
+                Ok(Err(e)) => Err(e),
+                _ => Err(anyhow!("Buy of {} stopped before anything was sent", mint)),
+            },
+        }
+    }
+
+    /// Attempt loop behind `buy_token`; `sent` fires with the first accepted signature
+    async fn run_buy<R, Fut>(
         &self,
         private_key: &str,
-        token_mint: &str,
-        amount_sol: f64,
-        slippage_bps: u64,
-        priority_fee_lamports: u64,
+        order: &BuyOrder,
         jupiter: &Arc<JupiterClient>,
-    ) -> Result<String> {
+        report: &R,
+        sent: oneshot::Sender<String>,
+    ) -> Result<ConfirmedBuy>
+    where
+        R: Fn(AttemptEvent) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
         if self.paper_trading {
-            info!("📝 PAPER TRADE: Bought {} with {} SOL", token_mint, amount_sol);
-            return Ok(format!("PAPER_TX_{}", chrono::Utc::now().timestamp()));
+            report(AttemptEvent::Started { attempt: 1, amount_sol: order.amount_sol }).await?;
+            info!("📝 PAPER TRADE: Bought {} with {} SOL", order.token_mint, order.amount_sol);
+            let signature = format!("PAPER_TX_{}", chrono::Utc::now().timestamp());
+            report(AttemptEvent::Sent { attempt: 1, signature: signature.clone() }).await.ok();
+            sent.send(signature.clone()).ok();
+            return Ok(ConfirmedBuy { signature, slot: 0 });
         }
 
-        // 1. Prepare (Build & Sign)
-        let tx = self.prepare_buy_transaction(
-            private_key,
-            token_mint,
-            amount_sol,
-            slippage_bps,
-            priority_fee_lamports,
-            jupiter
-        ).await?;
+        let keypair = Keypair::from_base58_string(private_key);
+        let started = Instant::now();
+        let mut sent = Some(sent);
+        let mut retries: HashMap<BuyErrorKind, u32> = HashMap::new();
+        let mut action = RetryAction::Requote;
+        let mut bump_pct = 0;
+        // Last built swap and signed transaction, reused by resend / re-sign
+        let mut last: Option<(VersionedMessage, VersionedTransaction)> = None;
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+
+            // 1. Prepare (Build & Sign) while recording the attempt
+            let (built, recorded) = tokio::join!(
+                self.build_attempt(action, &keypair, order, jupiter, last.as_ref(), bump_pct),
+                report(AttemptEvent::Started { attempt, amount_sol: order.amount_sol })
+            );
+            recorded.map_err(|e| anyhow!("Could not record buy attempt {}, not sending: {}", attempt, e))?;
+
+            // 2. Send (Hot path), then follow it until it is confirmed
+            let (signature, error) = match built {
+                Ok((message, tx)) => {
+                    let signature = tx.signatures[0].to_string();
+                    report(AttemptEvent::Sent { attempt, signature: signature.clone() }).await.ok();
+                    let submitted = self.submit(&tx, &keypair, order.tip_lamports).await;
+                    if submitted.is_ok() {
+                        if let Some(sent) = sent.take() {
+                            sent.send(signature.clone()).ok();
+                        }
+                    }
+                    let remaining = order.deadline.saturating_sub(started.elapsed());
+                    let followed = match submitted {
+                        Ok(submitted) => self.follow(submitted, &tx, remaining).await,
+                        Err(e) => Err(e),
+                    };
+                    last = Some((message, tx));
+                    let confirmed = match followed {
+                        Ok(status) => {
+                            report(AttemptEvent::Tracking { signature: signature.clone(), status: status.clone() }).await.ok();
+                            wait_confirmed(status).await
+                        }
+                        Err(e) => Err(e),
+                    };
+                    match confirmed {
+                        Ok(slot) => {
+                            info!("✅ Transaction confirmed in slot {}: {}", slot, signature);
+                            return Ok(ConfirmedBuy { signature, slot });
+                        }
+                        Err(e) => (Some(signature), e),
+                    }
+                }
+                Err(e) => (None, e),
+            };
+            warn!("⚠️ Buy attempt {} for {} failed: {}", attempt, order.token_mint, error);
+            report(AttemptEvent::Failed { signature, error: error.clone() }).await.ok();
 
-        // 2. Send (Hot path)
-        self.send_transaction(tx).await
+            // 3. Decide how (and whether) to try again
+            let policy = error.kind.policy();
+            let used = retries.entry(error.kind).or_default();
+            if policy.action == RetryAction::Abort || *used >= policy.max_retries {
+                return Err(anyhow!(error));
+            }
+            if started.elapsed() + policy.backoff >= order.deadline {
+                return Err(anyhow!("Buy deadline of {}s passed after {} attempt(s): {}", order.deadline.as_secs(), attempt, error));
+            }
+            *used += 1;
+            action = policy.action;
+            bump_pct = policy.priority_fee_bump_pct;
+            info!("🔁 Retrying buy of {} ({:?}, retry {}/{} for {})", order.token_mint, action, used, policy.max_retries, error.kind);
+            if !policy.backoff.is_zero() {
+                tokio::time::sleep(policy.backoff).await;
+            }
+        }
     }
 
+    /// Build the transaction for one attempt; falls back to a fresh quote
+    /// when there is nothing to resend or re-sign
+    async fn build_attempt(
+        &self,
+        action: RetryAction,
+        keypair: &Keypair,
+        order: &BuyOrder,
+        jupiter: &Arc<JupiterClient>,
+        last: Option<&(VersionedMessage, VersionedTransaction)>,
+        bump_pct: u64,
+    ) -> Result<(VersionedMessage, VersionedTransaction), BuyError> {
+        match (action, last) {
+            (RetryAction::Resend, Some((message, tx))) => Ok((message.clone(), tx.clone())),
+            (RetryAction::Resign, Some((message, _))) => {
+                let mut message = message.clone();
+                if bump_pct > 0 && !bump_compute_unit_price(&mut message, bump_pct) {
+                    warn!("⚠️ No compute unit price in the swap, re-signing without a fee bump");
+                }
+                let tx = self.sign(message.clone(), keypair).await?;
+                Ok((message, tx))
+            }
+            _ => self.prepare_buy_transaction(keypair, order, jupiter).await,
+        }
+    }
+
+    /// Quote, build and sign a fresh swap; returns the unsigned message for re-signing
     pub async fn prepare_buy_transaction(
         &self,
-        private_key: &str,
-        token_mint: &str,
-        amount_sol: f64,
-        slippage_bps: u64,
-        priority_fee_lamports: u64,
+        keypair: &Keypair,
+        order: &BuyOrder,
         jupiter: &Arc<JupiterClient>,
-    ) -> Result<VersionedTransaction> {
-        let keypair = Keypair::from_base58_string(private_key);
+    ) -> Result<(VersionedMessage, VersionedTransaction), BuyError> {
         let wallet_address = keypair.pubkey().to_string();
 
-        let amount_lamports = (amount_sol * 1_000_000_000.0) as u64;
-        let quote = jupiter.get_buy_quote(token_mint, amount_lamports, slippage_bps).await?;
+        let amount_lamports = (order.amount_sol * 1_000_000_000.0) as u64;
+        let quote = jupiter.get_buy_quote(&order.token_mint, amount_lamports, order.slippage_bps).await
+            .map_err(BuyError::jupiter)?;
         
-        let swap_tx_base64 = jupiter.get_swap_transaction(quote, &wallet_address, priority_fee_lamports).await?;
+        let swap_tx_base64 = jupiter.get_swap_transaction(quote, &wallet_address, order.priority_fee_lamports).await
+            .map_err(BuyError::jupiter)?;
         let versioned_tx_bytes = general_purpose::STANDARD.decode(&swap_tx_base64)
-            .map_err(|e| anyhow!("Failed to decode base64 tx: {}", e))?;
+            .map_err(|e| BuyError::new(BuyErrorKind::Jupiter, format!("Failed to decode base64 tx: {}", e)))?;
+
+        let versioned_tx: VersionedTransaction = bincode::deserialize(&versioned_tx_bytes)
+            .map_err(|e| BuyError::new(BuyErrorKind::Jupiter, format!("Failed to deserialize tx: {}", e)))?;
+
+        let signed_tx = self.sign(versioned_tx.message.clone(), keypair).await?;
+        Ok((versioned_tx.message, signed_tx))
+    }
+
+    /// Sign a message with the cached blockhash
+    async fn sign(&self, mut message: VersionedMessage, keypair: &Keypair) -> Result<VersionedTransaction, BuyError> {
+        let latest = self.blockhash.get().await?;
+        message.set_recent_blockhash(latest.blockhash);
+
+        VersionedTransaction::try_new(message, &[keypair])
+            .map_err(|e| BuyError::new(BuyErrorKind::Other, format!("Failed to sign tx: {}", e)))
+    }
+
+    /// Send as a Jito bundle when tipping, otherwise (or as a fallback) over RPC
+    async fn submit(&self, tx: &VersionedTransaction, keypair: &Keypair, tip_lamports: u64) -> Result<Submitted, BuyError> {
+        let jito = match &self.jito {
+            Some(jito) if tip_lamports > 0 => jito,
+            _ => return self.send_transaction(tx).await.map(Submitted::Tracked),
+        };
+        let tip_tx = tip_transaction(tx, keypair, tip_lamports);
+        match jito.send_bundle(&[tx.clone(), tip_tx]).await {
+            Ok(bundle_id) => {
+                info!("📦 Jito bundle {} sent with {} lamport tip", bundle_id, tip_lamports);
+                Ok(Submitted::Bundle(bundle_id))
+            }
+            Err(e) => self.bundle_failed(jito, tx, BuyError::new(BuyErrorKind::Jito, e.to_string())).await
+                .map(Submitted::Tracked),
+        }
+    }
+
+    /// Status of an accepted attempt; a bundle is given at most `remaining`
+    /// of the buy deadline to land
+    async fn follow(&self, submitted: Submitted, tx: &VersionedTransaction, remaining: Duration) -> Result<watch::Receiver<TxStatus>, BuyError> {
+        let bundle_id = match submitted {
+            Submitted::Tracked(status) => return Ok(status),
+            Submitted::Bundle(id) => id,
+        };
+        let jito = match &self.jito {
+            Some(jito) => jito,
+            None => return Ok(self.tracker.watch(tx)),
+        };
+        match jito.wait_for_bundle(&bundle_id, remaining).await {
+            Ok(()) => {
+                info!("✅ Jito bundle {} landed: {}", bundle_id, tx.signatures[0]);
+                Ok(self.tracker.watch(tx))
+            }
+            Err(e) => self.bundle_failed(jito, tx, BuyError::new(BuyErrorKind::Jito, e.to_string())).await,
+        }
+    }
+
+    /// After a bundle is rejected or times out: send the same swap over RPC,
+    /// or without the fallback, wait until it can no longer land
+    async fn bundle_failed(&self, jito: &JitoClient, tx: &VersionedTransaction, error: BuyError) -> Result<watch::Receiver<TxStatus>, BuyError> {
+        // The same signed swap, so it can't land twice
+        if jito.config.fallback_to_rpc {
+            warn!("⚠️ {}, falling back to RPC send", error);
+            return self.send_transaction(tx).await;
+        }
+        // The bundle may still land; re-signing before the swap expires could buy twice
+        warn!("⚠️ {}, waiting for the swap to expire before re-signing", error);
+        let status = self.tracker.watch(tx);
+        match wait_confirmed(status.clone()).await {
+            Ok(_) => Ok(status),
+            Err(expired) if expired.kind == BuyErrorKind::BlockhashNotFound => Err(error),
+            Err(other) => Err(other),
+        }
+    }
+
+    /// Send without preflight and hand the signature to the confirmation
+    /// tracker, which keeps re-broadcasting it until it lands or expires
+    pub async fn send_transaction(&self, tx: &VersionedTransaction) -> Result<watch::Receiver<TxStatus>, BuyError> {
+        let signature = self.broadcaster.broadcast(tx).await?;
+        info!("📨 Transaction sent: {}", signature);
+        Ok(self.tracker.track(tx))
+    }
+}
+
+/// Wait until a sent transaction is confirmed, fails, or expires; one that
+/// is processed and then dropped expires like any other. Returns the landing slot.
+async fn wait_confirmed(mut status: watch::Receiver<TxStatus>) -> Result<u64, BuyError> {
+    loop {
+        let current = status.borrow_and_update().clone();
+        match current {
+            TxStatus::Confirmed { slot } | TxStatus::Finalized { slot } => return Ok(slot),
+            TxStatus::Failed(e) => return Err(BuyError::from_transaction(&e)),
+            TxStatus::Expired => {
+                return Err(BuyError::new(BuyErrorKind::BlockhashNotFound, "Blockhash expired before the transaction landed"));
+            }
+            _ => {}
+        }
+        if status.changed().await.is_err() {
+            return Err(BuyError::new(BuyErrorKind::RpcTimeout, "Confirmation tracker stopped"));
+        }
+    }
+}
+
+/// Tip transfer to a Jito tip account, signed with the swap's blockhash
+fn tip_transaction(swap: &VersionedTransaction, keypair: &Keypair, tip_lamports: u64) -> VersionedTransaction {
+    let index = swap.signatures[0].as_ref()[0] as usize % JITO_TIP_ACCOUNTS.len();
+    let tip_account = Pubkey::from_str(JITO_TIP_ACCOUNTS[index])
+        .expect("Jito tip accounts are valid");
+    let transfer = system_instruction::transfer(&keypair.pubkey(), &tip_account, tip_lamports);
+    let tip = Transaction::new_signed_with_payer(
+        &[transfer],
+        Some(&keypair.pubkey()),
+        &[keypair],
+        *swap.message.recent_blockhash(),
+    );
+    VersionedTransaction::from(tip)
+}
+
+/// Raise the ComputeBudget unit price of a message by `pct` percent
+fn bump_compute_unit_price(message: &mut VersionedMessage, pct: u64) -> bool {
+    let compute_budget = solana_sdk::compute_budget::id();
+    let (keys, instructions) = match message {
+        VersionedMessage::Legacy(m) => (&m.account_keys, &mut m.instructions),
+        VersionedMessage::V0(m) => (&m.account_keys, &mut m.instructions),
+    };
+    for ix in instructions.iter_mut() {
+        if keys.get(ix.program_id_index as usize) != Some(&compute_budget)
+            || ix.data.len() != 9
+            || ix.data[0] != SET_COMPUTE_UNIT_PRICE
+        {
+            continue;
+        }
+        let price = u64::from_le_bytes(ix.data[1..9].try_into().unwrap_or_default());
+        let bumped = price + price * pct / 100;
+        ix.data[1..9].copy_from_slice(&bumped.to_le_bytes());
+        info!("⛽ Priority fee bumped {}%: {} -> {} µlamports/CU", pct, price, bumped);
+        return true;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::hash::Hash;
+    use std::sync::Mutex;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Block engine stand-in: answers each JSON-RPC call with the canned
+    /// reply for its method and records the request bodies
+    async fn mock_block_engine(replies: Vec<(&'static str, serde_json::Value)>) -> (String, Arc<Mutex<Vec<serde_json::Value>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = match listener.accept().await {
+                    Ok(conn) => conn,
+                    Err(_) => return,
+                };
+                let mut buf = Vec::new();
+                let mut chunk = [0u8; 4096];
+                let body_start = loop {
+                    let n = socket.read(&mut chunk).await.unwrap();
+                    buf.extend_from_slice(&chunk[..n]);
+                    if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break pos + 4;
+                    }
+                };
+                let headers = String::from_utf8_lossy(&buf[..body_start]).to_lowercase();
+                let length: usize = headers.lines()
+                    .find_map(|l| l.strip_prefix("content-length:"))
+                    .and_then(|v| v.trim().parse().ok())
+                    .unwrap_or(0);
+                while buf.len() < body_start + length {
+                    let n = socket.read(&mut chunk).await.unwrap();
+                    buf.extend_from_slice(&chunk[..n]);
+                }
+                let request: serde_json::Value = serde_json::from_slice(&buf[body_start..]).unwrap();
+                let reply = replies.iter()
+                    .find(|(method, _)| request["method"] == *method)
+                    .map(|(_, reply)| reply.clone())
+                    .unwrap_or_else(|| serde_json::json!({ "jsonrpc": "2.0", "id": 1, "error": { "code": -32601, "message": "Method not found" } }));
+                seen.lock().unwrap().push(request);
+                let body = reply.to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.ok();
+            }
+        });
+        (url, requests)
+    }
 
-        let mut versioned_tx: VersionedTransaction = bincode::deserialize(&versioned_tx_bytes)
-            .map_err(|e| anyhow!("Failed to deserialize tx: {}", e))?;
+    fn client(url: String) -> JitoClient {
+        JitoClient::new(JitoConfig {
+            block_engine_url: url,
+            fallback_to_rpc: true,
+            bundle_timeout: Duration::from_secs(60),
+        })
+    }
 
-        let latest_blockhash = self.rpc_client.get_latest_blockhash().await?;
-        versioned_tx.message.set_recent_blockhash(latest_blockhash);
+    fn signed_swap(keypair: &Keypair) -> VersionedTransaction {
+        let transfer = system_instruction::transfer(&keypair.pubkey(), &Pubkey::new_unique(), 1);
+        let tx = Transaction::new_signed_with_payer(&[transfer], Some(&keypair.pubkey()), &[keypair], Hash::new_unique());
+        VersionedTransaction::from(tx)
+    }
 
-        let signed_tx = VersionedTransaction::try_new(versioned_tx.message, &[&keypair])?;
-        Ok(signed_tx)
+    fn message_with(instructions: &[solana_sdk::instruction::Instruction], payer: &Pubkey) -> VersionedMessage {
+        VersionedMessage::Legacy(solana_sdk::message::Message::new(instructions, Some(payer)))
     }
 
-    pub async fn send_transaction(&self, tx: VersionedTransaction) -> Result<String> {
-        let signature = self.rpc_client.send_and_confirm_transaction(&tx).await?;
-        info!("✅ Transaction confirmed: {}", signature);
-        Ok(signature.to_string())
+    fn unit_price(message: &VersionedMessage) -> Option<u64> {
+        let keys = message.static_account_keys();
+        message.instructions().iter()
+            .find(|ix| keys[ix.program_id_index as usize] == solana_sdk::compute_budget::id() && ix.data[0] == SET_COMPUTE_UNIT_PRICE)
+            .map(|ix| u64::from_le_bytes(ix.data[1..9].try_into().unwrap()))
+    }
+
+    #[test]
+    fn bump_raises_only_the_unit_price() {
+        use solana_sdk::compute_budget::ComputeBudgetInstruction;
+        let payer = Pubkey::new_unique();
+        let transfer = system_instruction::transfer(&payer, &Pubkey::new_unique(), 1);
+        let mut message = message_with(&[
+            ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+            ComputeBudgetInstruction::set_compute_unit_price(1_000),
+            transfer.clone(),
+        ], &payer);
+        let before = message.clone();
+
+        assert!(bump_compute_unit_price(&mut message, 25));
+        assert_eq!(unit_price(&message), Some(1_250));
+        // Everything else is left alone
+        assert_eq!(message.instructions()[0], before.instructions()[0]);
+        assert_eq!(message.instructions()[2], before.instructions()[2]);
+
+        assert!(bump_compute_unit_price(&mut message, 100));
+        assert_eq!(unit_price(&message), Some(2_500));
+    }
+
+    #[test]
+    fn bump_without_a_unit_price_changes_nothing() {
+        use solana_sdk::compute_budget::ComputeBudgetInstruction;
+        let payer = Pubkey::new_unique();
+        let mut message = message_with(&[
+            ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+            system_instruction::transfer(&payer, &Pubkey::new_unique(), 1),
+        ], &payer);
+        let before = message.clone();
+
+        assert!(!bump_compute_unit_price(&mut message, 25));
+        assert_eq!(message, before);
+    }
+
+    #[test]
+    fn bump_of_zero_price_stays_zero() {
+        use solana_sdk::compute_budget::ComputeBudgetInstruction;
+        let payer = Pubkey::new_unique();
+        let mut message = message_with(&[ComputeBudgetInstruction::set_compute_unit_price(0)], &payer);
+        assert!(bump_compute_unit_price(&mut message, 25));
+        assert_eq!(unit_price(&message), Some(0));
+    }
+
+    #[tokio::test]
+    async fn send_bundle_posts_base64_transactions_and_returns_id() {
+        let (url, requests) = mock_block_engine(vec![
+            ("sendBundle", serde_json::json!({ "jsonrpc": "2.0", "id": 1, "result": "bundle-1" })),
+        ]).await;
+        let keypair = Keypair::new();
+        let swap = signed_swap(&keypair);
+        let tip = tip_transaction(&swap, &keypair, 10_000);
+
+        let id = client(url).send_bundle(&[swap.clone(), tip]).await.unwrap();
+        assert_eq!(id, "bundle-1");
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        let params = &requests[0]["params"];
+        assert_eq!(params[1]["encoding"], "base64");
+        let sent = params[0].as_array().unwrap();
+        assert_eq!(sent.len(), 2);
+        let first: VersionedTransaction = bincode::deserialize(
+            &general_purpose::STANDARD.decode(sent[0].as_str().unwrap()).unwrap()
+        ).unwrap();
+        assert_eq!(first.signatures[0], swap.signatures[0]);
+    }
+
+    #[tokio::test]
+    async fn send_bundle_surfaces_rpc_errors() {
+        let (url, _) = mock_block_engine(vec![
+            ("sendBundle", serde_json::json!({ "jsonrpc": "2.0", "id": 1, "error": { "code": -32602, "message": "bundle contains an already processed transaction" } })),
+        ]).await;
+        let keypair = Keypair::new();
+
+        let err = client(url).send_bundle(&[signed_swap(&keypair)]).await.unwrap_err();
+        assert!(err.to_string().contains("already processed"));
+    }
+
+    #[tokio::test]
+    async fn bundle_status_reads_the_inflight_status() {
+        let (url, requests) = mock_block_engine(vec![
+            ("getInflightBundleStatuses", serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": { "context": { "slot": 1 }, "value": [{ "bundle_id": "bundle-1", "status": "Landed", "landed_slot": 1 }] }
+            })),
+        ]).await;
+
+        let status = client(url).bundle_status("bundle-1").await.unwrap();
+        assert_eq!(status, "Landed");
+        assert_eq!(requests.lock().unwrap()[0]["params"], serde_json::json!([["bundle-1"]]));
+    }
+
+    #[tokio::test]
+    async fn wait_for_bundle_stops_at_the_limit() {
+        let (url, _) = mock_block_engine(vec![
+            ("getInflightBundleStatuses", serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": { "context": { "slot": 1 }, "value": [{ "bundle_id": "bundle-1", "status": "Pending", "landed_slot": null }] }
+            })),
+        ]).await;
+
+        let started = Instant::now();
+        let result = client(url).wait_for_bundle("bundle-1", Duration::from_millis(600)).await;
+        assert!(result.is_err());
+        assert!(started.elapsed() < Duration::from_secs(5));
     }
 }
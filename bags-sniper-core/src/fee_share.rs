@@ -0,0 +1,641 @@
+//! IDL-driven decoder for Bags Fee Share instructions
+//!
+//! Discriminators, account order and argument layouts are read from the
+//! fee-share-v2 and legacy V1 (`bags-meteora-fee-claimer`) IDLs shipped in
+//! `bags-sdk-main`, so an IDL update only needs a rebuild. Claim instructions decode into typed events with
+//! named accounts. The decoder itself only depends on the IDL, so other
+//! Anchor programs (e.g. the DBC launchpad in `launch`) reuse it through
+//! `decode_instruction`.
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+
+const FEE_SHARE_V2_IDL: &str = include_str!("../../bags-sdk-main/src/idl/fee-share-v2/idl.json");
+const FEE_SHARE_V1_IDL: &str = include_str!("../../bags-sdk-main/src/idl/bags-meteora-fee-claimer/idl.json");
+
+#[derive(Debug, Deserialize)]
+struct Idl {
+    address: String,
+    instructions: Vec<IdlInstruction>,
+    #[serde(default)]
+    types: Vec<IdlTypeDef>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdlInstruction {
+    name: String,
+    discriminator: Vec<u8>,
+    accounts: Vec<IdlAccount>,
+    #[serde(default)]
+    args: Vec<IdlField>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdlAccount {
+    name: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct IdlField {
+    name: String,
+    #[serde(rename = "type")]
+    ty: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdlTypeDef {
+    name: String,
+    #[serde(rename = "type")]
+    ty: IdlTypeBody,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdlTypeBody {
+    kind: String,
+    #[serde(default)]
+    fields: Vec<IdlField>,
+}
+
+/// Decoded primitive argument value
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgValue {
+    Bool(bool),
+    U64(u64),
+    I64(i64),
+    Pubkey(String),
+    String(String),
+}
+
+/// Any instruction decoded through its IDL layout
+#[derive(Debug, Clone)]
+pub struct DecodedInstruction {
+    pub name: String,
+    /// IDL account name -> resolved address
+    pub accounts: HashMap<String, String>,
+    /// Flattened argument name -> value
+    pub args: HashMap<String, ArgValue>,
+}
+
+impl DecodedInstruction {
+    pub fn account(&self, name: &str) -> Option<&str> {
+        self.accounts.get(name).map(|k| k.as_str())
+    }
+
+    pub fn u64_arg(&self, name: &str) -> Option<u64> {
+        match self.args.get(name)? {
+            ArgValue::U64(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn string_arg(&self, name: &str) -> Option<&str> {
+        match self.args.get(name)? {
+            ArgValue::String(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+/// Arguments of `claim_user`
+#[derive(Debug, Clone)]
+pub struct ClaimUserArgs {
+    /// Index of claimer in the config claimers array
+    pub claimer_index: u32,
+}
+
+/// `claim_damm_v2`: protocol fee distribution from a DAMM v2 position
+#[derive(Debug, Clone)]
+pub struct ClaimDamm {
+    pub payer: String,
+    pub fee_share_config: String,
+    pub fee_share_authority: String,
+    pub fee_share_authority_quote_ata: String,
+    pub partner: String,
+    pub base_mint: String,
+    pub quote_mint: String,
+    pub pool: String,
+}
+
+/// `claim_dbc`: protocol fee distribution from a DBC pool
+#[derive(Debug, Clone)]
+pub struct ClaimDbc {
+    pub payer: String,
+    pub fee_share_config: String,
+    pub fee_share_authority: String,
+    pub fee_share_authority_quote_ata: String,
+    pub partner: String,
+    pub base_mint: String,
+    pub quote_mint: String,
+    pub pool: String,
+}
+
+/// `claim_user`: a fee claimer withdrawing their share
+#[derive(Debug, Clone)]
+pub struct ClaimUser {
+    pub payer: String,
+    pub user: String,
+    pub user_quote_ata: String,
+    pub fee_share_config: String,
+    pub fee_share_authority: String,
+    pub base_mint: String,
+    pub quote_mint: String,
+    pub args: ClaimUserArgs,
+}
+
+/// `claim_partner`: a partner withdrawing accumulated partner fees (not mint specific)
+#[derive(Debug, Clone)]
+pub struct ClaimPartner {
+    pub payer: String,
+    pub partner: String,
+    pub partner_config: String,
+    pub partner_quote_ata: String,
+    pub quote_mint: String,
+}
+
+/// V1 `claim_a`/`claim_b` (DBC) and `claim_damm_a`/`claim_damm_b`: one of
+/// the two fee claimers withdrawing their share from their fee vault
+#[derive(Debug, Clone)]
+pub struct ClaimV1 {
+    /// The claimer withdrawing (the only signer)
+    pub claimer: String,
+    /// The claimer's fee vault, paid out to the claimer as SOL
+    pub vault: String,
+    pub fee_authority: String,
+    pub base_mint: String,
+    pub quote_mint: String,
+    pub pool: String,
+}
+
+/// A decoded Bags fee-share claim
+#[derive(Debug, Clone)]
+pub enum FeeShareClaim {
+    Damm(ClaimDamm),
+    Dbc(ClaimDbc),
+    User(ClaimUser),
+    Partner(ClaimPartner),
+    V1(ClaimV1),
+}
+
+impl FeeShareClaim {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            FeeShareClaim::Damm(_) => "DAMM_V2",
+            FeeShareClaim::Dbc(_) => "DBC",
+            FeeShareClaim::User(_) => "CLAIM_USER",
+            FeeShareClaim::Partner(_) => "CLAIM_PARTNER",
+            FeeShareClaim::V1(_) => "V1_CLAIM",
+        }
+    }
+
+    pub fn payer(&self) -> &str {
+        match self {
+            FeeShareClaim::Damm(c) => &c.payer,
+            FeeShareClaim::Dbc(c) => &c.payer,
+            FeeShareClaim::User(c) => &c.payer,
+            FeeShareClaim::Partner(c) => &c.payer,
+            FeeShareClaim::V1(c) => &c.claimer,
+        }
+    }
+
+    /// The token the fees were generated by (None for partner claims)
+    pub fn base_mint(&self) -> Option<&str> {
+        match self {
+            FeeShareClaim::Damm(c) => Some(&c.base_mint),
+            FeeShareClaim::Dbc(c) => Some(&c.base_mint),
+            FeeShareClaim::User(c) => Some(&c.base_mint),
+            FeeShareClaim::Partner(_) => None,
+            FeeShareClaim::V1(c) => Some(&c.base_mint),
+        }
+    }
+
+    pub fn quote_mint(&self) -> &str {
+        match self {
+            FeeShareClaim::Damm(c) => &c.quote_mint,
+            FeeShareClaim::Dbc(c) => &c.quote_mint,
+            FeeShareClaim::User(c) => &c.quote_mint,
+            FeeShareClaim::Partner(c) => &c.quote_mint,
+            FeeShareClaim::V1(c) => &c.quote_mint,
+        }
+    }
+
+    pub fn fee_share_config(&self) -> Option<&str> {
+        match self {
+            FeeShareClaim::Damm(c) => Some(&c.fee_share_config),
+            FeeShareClaim::Dbc(c) => Some(&c.fee_share_config),
+            FeeShareClaim::User(c) => Some(&c.fee_share_config),
+            FeeShareClaim::Partner(_) | FeeShareClaim::V1(_) => None,
+        }
+    }
+
+    /// Wallets that signed the claim
+    pub fn signers(&self) -> Vec<&str> {
+        match self {
+            FeeShareClaim::User(c) => vec![&c.payer, &c.user],
+            FeeShareClaim::Partner(c) => vec![&c.payer, &c.partner],
+            _ => vec![self.payer()],
+        }
+    }
+
+    /// Wallet (or authority PDA) receiving the claimed fees, and its quote token account
+    pub fn recipient(&self) -> (&str, &str) {
+        match self {
+            FeeShareClaim::Damm(c) => (&c.fee_share_authority, &c.fee_share_authority_quote_ata),
+            FeeShareClaim::Dbc(c) => (&c.fee_share_authority, &c.fee_share_authority_quote_ata),
+            FeeShareClaim::User(c) => (&c.user, &c.user_quote_ata),
+            FeeShareClaim::Partner(c) => (&c.partner, &c.partner_quote_ata),
+            FeeShareClaim::V1(c) => (&c.claimer, &c.vault),
+        }
+    }
+
+    /// Protocol or partner distribution rather than a claimer withdrawing
+    pub fn is_distribution(&self) -> bool {
+        !self.is_claimer_withdrawal()
+    }
+
+    /// A fee claimer (creator or other share holder) withdrawing their own share
+    pub fn is_claimer_withdrawal(&self) -> bool {
+        matches!(self, FeeShareClaim::User(_) | FeeShareClaim::V1(_))
+    }
+
+    pub fn pool(&self) -> Option<&str> {
+        match self {
+            FeeShareClaim::Damm(c) => Some(&c.pool),
+            FeeShareClaim::Dbc(c) => Some(&c.pool),
+            FeeShareClaim::V1(c) => Some(&c.pool),
+            FeeShareClaim::User(_) | FeeShareClaim::Partner(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for FeeShareClaim {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} | Base: {} | Quote: {} | Config: {} | Pool: {} | Payer: {}",
+            self.kind(),
+            self.base_mint().unwrap_or("-"),
+            self.quote_mint(),
+            self.fee_share_config().unwrap_or("-"),
+            self.pool().unwrap_or("-"),
+            self.payer()
+        )?;
+        match self {
+            FeeShareClaim::Damm(c) => write!(f, " | Authority: {} | Partner: {}", c.fee_share_authority, c.partner),
+            FeeShareClaim::Dbc(c) => write!(f, " | Authority: {} | Partner: {}", c.fee_share_authority, c.partner),
+            FeeShareClaim::User(c) => write!(
+                f,
+                " | User: {} | Authority: {} | Claimer Index: {}",
+                c.user, c.fee_share_authority, c.args.claimer_index
+            ),
+            FeeShareClaim::Partner(c) => write!(f, " | Partner: {} | Partner Config: {}", c.partner, c.partner_config),
+            FeeShareClaim::V1(c) => write!(f, " | Claimer: {} | Authority: {} | Vault: {}", c.claimer, c.fee_authority, c.vault),
+        }
+    }
+}
+
+/// Instruction layout looked up by discriminator
+struct InstructionLayout {
+    name: String,
+    accounts: Vec<String>,
+    args: Vec<IdlField>,
+}
+
+pub struct FeeShareDecoder {
+    program_id: String,
+    by_discriminator: HashMap<[u8; 8], InstructionLayout>,
+    types: HashMap<String, Vec<IdlField>>,
+}
+
+impl FeeShareDecoder {
+    /// Build a decoder from Anchor IDL JSON
+    pub fn from_idl(json: &str) -> Result<Self> {
+        let idl: Idl = serde_json::from_str(json)?;
+
+        let mut by_discriminator = HashMap::new();
+        for ix in idl.instructions {
+            let disc: [u8; 8] = ix.discriminator.as_slice().try_into()
+                .map_err(|_| anyhow!("Instruction {} has a bad discriminator", ix.name))?;
+            by_discriminator.insert(disc, InstructionLayout {
+                name: ix.name,
+                accounts: ix.accounts.into_iter().map(|a| a.name).collect(),
+                args: ix.args,
+            });
+        }
+
+        let types = idl.types.into_iter()
+            .filter(|t| t.ty.kind == "struct")
+            .map(|t| (t.name, t.ty.fields))
+            .collect();
+
+        Ok(Self {
+            program_id: idl.address,
+            by_discriminator,
+            types,
+        })
+    }
+
+    /// Decoder for the fee-share-v2 IDL bundled at build time
+    pub fn v2() -> &'static FeeShareDecoder {
+        static DECODER: OnceLock<FeeShareDecoder> = OnceLock::new();
+        DECODER.get_or_init(|| {
+            FeeShareDecoder::from_idl(FEE_SHARE_V2_IDL).expect("Bundled fee-share-v2 IDL is invalid")
+        })
+    }
+
+    /// Decoder for the legacy V1 fee claimer IDL bundled at build time
+    pub fn v1() -> &'static FeeShareDecoder {
+        static DECODER: OnceLock<FeeShareDecoder> = OnceLock::new();
+        DECODER.get_or_init(|| {
+            FeeShareDecoder::from_idl(FEE_SHARE_V1_IDL).expect("Bundled bags-meteora-fee-claimer IDL is invalid")
+        })
+    }
+
+    /// The bundled decoder for a Bags fee-share program, if it is one
+    pub fn for_program(program_id: &str) -> Option<&'static FeeShareDecoder> {
+        [Self::v2(), Self::v1()].into_iter().find(|d| d.program_id() == program_id)
+    }
+
+    pub fn program_id(&self) -> &str {
+        &self.program_id
+    }
+
+    /// IDL name of the instruction, if the discriminator is known
+    pub fn instruction_name(&self, data: &[u8]) -> Option<&str> {
+        let disc: [u8; 8] = data.get(..8)?.try_into().ok()?;
+        self.by_discriminator.get(&disc).map(|l| l.name.as_str())
+    }
+
+    /// Decode any known instruction into named accounts and args.
+    /// `accounts` are the resolved addresses in instruction order.
+    pub fn decode_instruction(&self, data: &[u8], accounts: &[String]) -> Option<DecodedInstruction> {
+        let disc: [u8; 8] = data.get(..8)?.try_into().ok()?;
+        let layout = self.by_discriminator.get(&disc)?;
+        Some(DecodedInstruction {
+            name: layout.name.clone(),
+            accounts: layout.accounts.iter().cloned().zip(accounts.iter().cloned()).collect(),
+            args: self.decode_args(&layout.args, &data[8..])?,
+        })
+    }
+
+    /// Decode a claim instruction. `accounts` are the resolved addresses in instruction order.
+    pub fn decode_claim(&self, data: &[u8], accounts: &[String]) -> Option<FeeShareClaim> {
+        let disc: [u8; 8] = data.get(..8)?.try_into().ok()?;
+        let layout = self.by_discriminator.get(&disc)?;
+
+        let named: HashMap<&str, &str> = layout.accounts.iter()
+            .zip(accounts.iter())
+            .map(|(name, key)| (name.as_str(), key.as_str()))
+            .collect();
+        let account = |name: &str| named.get(name).map(|k| k.to_string());
+
+        match layout.name.as_str() {
+            "claim_damm_v2" => Some(FeeShareClaim::Damm(ClaimDamm {
+                payer: account("payer")?,
+                fee_share_config: account("fee_share_config")?,
+                fee_share_authority: account("fee_share_authority")?,
+                fee_share_authority_quote_ata: account("fee_share_authority_quote_ata")?,
+                partner: account("partner")?,
+                base_mint: account("base_mint")?,
+                quote_mint: account("quote_mint")?,
+                pool: account("pool")?,
+            })),
+            "claim_dbc" => Some(FeeShareClaim::Dbc(ClaimDbc {
+                payer: account("payer")?,
+                fee_share_config: account("fee_share_config")?,
+                fee_share_authority: account("fee_share_authority")?,
+                fee_share_authority_quote_ata: account("fee_share_authority_quote_ata")?,
+                partner: account("partner")?,
+                base_mint: account("base_mint")?,
+                quote_mint: account("quote_mint")?,
+                pool: account("pool")?,
+            })),
+            "claim_user" => {
+                let args = self.decode_args(&layout.args, &data[8..])?;
+                let claimer_index = match args.get("claimer_index") {
+                    Some(ArgValue::U64(v)) => *v as u32,
+                    _ => return None,
+                };
+                Some(FeeShareClaim::User(ClaimUser {
+                    payer: account("payer")?,
+                    user: account("user")?,
+                    user_quote_ata: account("user_quote_ata")?,
+                    fee_share_config: account("fee_share_config")?,
+                    fee_share_authority: account("fee_share_authority")?,
+                    base_mint: account("base_mint")?,
+                    quote_mint: account("quote_mint")?,
+                    args: ClaimUserArgs { claimer_index },
+                }))
+            }
+            "claim_partner" => Some(FeeShareClaim::Partner(ClaimPartner {
+                payer: account("payer")?,
+                partner: account("partner")?,
+                partner_config: account("partner_config")?,
+                partner_quote_ata: account("partner_quote_ata")?,
+                quote_mint: account("quote_mint")?,
+            })),
+            // V1: the `_a` and `_b` variants differ only in which claimer signs
+            "claim_a" | "claim_damm_a" | "claim_b" | "claim_damm_b" => {
+                let (claimer, vault) = if layout.name.ends_with("_a") {
+                    ("claimer_a", "vault_a")
+                } else {
+                    ("claimer_b", "vault_b")
+                };
+                Some(FeeShareClaim::V1(ClaimV1 {
+                    claimer: account(claimer)?,
+                    vault: account(vault)?,
+                    fee_authority: account("fee_authority")?,
+                    base_mint: account("base_mint")?,
+                    quote_mint: account("quote_mint")?,
+                    pool: account("pool")?,
+                }))
+            }
+            _ => None,
+        }
+    }
+
+    /// Borsh-decode instruction args into a flat name -> value map.
+    /// Nested defined structs are flattened into their field names.
+    fn decode_args(&self, fields: &[IdlField], data: &[u8]) -> Option<HashMap<String, ArgValue>> {
+        let mut values = HashMap::new();
+        let mut offset = 0;
+        self.decode_fields(fields, data, &mut offset, &mut values)?;
+        Some(values)
+    }
+
+    fn decode_fields(
+        &self,
+        fields: &[IdlField],
+        data: &[u8],
+        offset: &mut usize,
+        out: &mut HashMap<String, ArgValue>,
+    ) -> Option<()> {
+        for field in fields {
+            if let Some(defined) = field.ty.get("defined").and_then(|d| d.get("name")).and_then(|n| n.as_str()) {
+                let nested = self.types.get(defined)?;
+                self.decode_fields(nested, data, offset, out)?;
+                continue;
+            }
+
+            let value = match field.ty.as_str()? {
+                "bool" => ArgValue::Bool(read_bytes(data, offset, 1)?[0] != 0),
+                "u8" => ArgValue::U64(read_bytes(data, offset, 1)?[0] as u64),
+                "u16" => ArgValue::U64(u16::from_le_bytes(read_bytes(data, offset, 2)?.try_into().ok()?) as u64),
+                "u32" => ArgValue::U64(u32::from_le_bytes(read_bytes(data, offset, 4)?.try_into().ok()?) as u64),
+                "u64" => ArgValue::U64(u64::from_le_bytes(read_bytes(data, offset, 8)?.try_into().ok()?)),
+                "i64" => ArgValue::I64(i64::from_le_bytes(read_bytes(data, offset, 8)?.try_into().ok()?)),
+                "pubkey" => ArgValue::Pubkey(bs58::encode(read_bytes(data, offset, 32)?).into_string()),
+                "string" => {
+                    let len = u32::from_le_bytes(read_bytes(data, offset, 4)?.try_into().ok()?) as usize;
+                    ArgValue::String(String::from_utf8(read_bytes(data, offset, len)?.to_vec()).ok()?)
+                }
+                _ => return None, // Layout we can't walk past
+            };
+            out.insert(field.name.clone(), value);
+        }
+        Some(())
+    }
+}
+
+fn read_bytes<'a>(data: &'a [u8], offset: &mut usize, len: usize) -> Option<&'a [u8]> {
+    let bytes = data.get(*offset..*offset + len)?;
+    *offset += len;
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLAIM_USER: [u8; 8] = [164, 64, 55, 199, 90, 78, 147, 188];
+    const CLAIM_DAMM_V2: [u8; 8] = [232, 175, 106, 19, 168, 54, 186, 108];
+    const CLAIM_DBC: [u8; 8] = [229, 142, 38, 65, 198, 50, 110, 58];
+    const CLAIM_B: [u8; 8] = [213, 21, 159, 131, 21, 57, 2, 67];
+    const CLAIM_DAMM_A: [u8; 8] = [23, 251, 214, 27, 55, 68, 16, 87];
+
+    /// A distinct address per account name
+    fn key(name: &str) -> String {
+        let mut bytes = [0u8; 32];
+        bytes[..name.len()].copy_from_slice(name.as_bytes());
+        bs58::encode(bytes).into_string()
+    }
+
+    fn accounts(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| key(n)).collect()
+    }
+
+    const CLAIM_USER_ACCOUNTS: [&str; 13] = [
+        "payer", "user", "fee_share_config", "fee_share_authority", "fee_share_authority_quote_ata",
+        "user_quote_ata", "base_mint", "quote_mint", "system_program", "token_program",
+        "associated_token_program", "event_authority", "program",
+    ];
+
+    const CLAIM_DAMM_V2_ACCOUNTS: [&str; 27] = [
+        "payer", "fee_share_config", "fee_share_authority", "fee_share_authority_quote_ata",
+        "fee_share_authority_base_ata", "program_config", "platform_vault", "partner_config", "partner",
+        "partner_config_quote_ata", "base_mint", "quote_mint", "token_program", "associated_token_program",
+        "damm_program", "pool_authority", "pool", "position", "base_vault", "quote_vault",
+        "position_nft_account", "damm_event_authority", "token_base_program", "token_quote_program",
+        "system_program", "event_authority", "program",
+    ];
+
+    const CLAIM_DBC_ACCOUNTS: [&str; 26] = [
+        "payer", "fee_share_config", "fee_share_authority", "fee_share_authority_quote_ata",
+        "fee_share_authority_base_ata", "partner_config", "partner", "partner_config_quote_ata",
+        "program_config", "platform_vault", "base_mint", "quote_mint", "token_program",
+        "associated_token_program", "dbc_program", "pool_authority", "config", "pool", "base_vault",
+        "quote_vault", "dbc_event_authority", "token_base_program", "token_quote_program",
+        "system_program", "event_authority", "program",
+    ];
+
+    /// V1 `claim_b`; `claim_a` swaps the first two
+    const CLAIM_B_ACCOUNTS: [&str; 22] = [
+        "claimer_b", "claimer_a", "fee_authority", "fee_authority_quote_ata", "fee_authority_base_ata",
+        "vault_a", "vault_b", "base_mint", "quote_mint", "system_program", "token_base_program",
+        "token_quote_program", "associated_token_program", "dbc_program", "pool_authority", "config",
+        "pool", "base_vault", "quote_vault", "dbc_event_authority", "event_authority", "program",
+    ];
+
+    const CLAIM_DAMM_A_ACCOUNTS: [&str; 23] = [
+        "claimer_a", "claimer_b", "fee_authority", "fee_authority_quote_ata", "fee_authority_base_ata",
+        "vault_a", "vault_b", "base_mint", "quote_mint", "system_program", "token_base_program",
+        "token_quote_program", "associated_token_program", "damm_program", "pool_authority", "pool",
+        "position", "base_vault", "quote_vault", "position_nft_account", "damm_event_authority",
+        "event_authority", "program",
+    ];
+
+    #[test]
+    fn decodes_claim_user() {
+        let mut data = CLAIM_USER.to_vec();
+        data.extend_from_slice(&3u32.to_le_bytes());
+        let claim = FeeShareDecoder::v2().decode_claim(&data, &accounts(&CLAIM_USER_ACCOUNTS)).unwrap();
+
+        assert_eq!(claim.kind(), "CLAIM_USER");
+        assert_eq!(claim.base_mint(), Some(key("base_mint").as_str()));
+        assert_eq!(claim.signers(), [key("payer"), key("user")]);
+        assert_eq!(claim.fee_share_config(), Some(key("fee_share_config").as_str()));
+        assert_eq!(claim.recipient(), (key("user").as_str(), key("user_quote_ata").as_str()));
+        assert!(!claim.is_distribution());
+        match claim {
+            FeeShareClaim::User(c) => assert_eq!(c.args.claimer_index, 3),
+            other => panic!("expected claim_user, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn claim_user_without_args_is_rejected() {
+        assert!(FeeShareDecoder::v2().decode_claim(&CLAIM_USER, &accounts(&CLAIM_USER_ACCOUNTS)).is_none());
+    }
+
+    #[test]
+    fn decodes_claim_damm_v2() {
+        let claim = FeeShareDecoder::v2().decode_claim(&CLAIM_DAMM_V2, &accounts(&CLAIM_DAMM_V2_ACCOUNTS)).unwrap();
+
+        assert_eq!(claim.kind(), "DAMM_V2");
+        assert_eq!(claim.base_mint(), Some(key("base_mint").as_str()));
+        assert_eq!(claim.quote_mint(), key("quote_mint"));
+        assert_eq!(claim.signers(), [key("payer")]);
+        assert_eq!(claim.pool(), Some(key("pool").as_str()));
+        assert!(claim.is_distribution());
+    }
+
+    #[test]
+    fn decodes_claim_dbc() {
+        let claim = FeeShareDecoder::v2().decode_claim(&CLAIM_DBC, &accounts(&CLAIM_DBC_ACCOUNTS)).unwrap();
+
+        assert_eq!(claim.kind(), "DBC");
+        assert_eq!(claim.base_mint(), Some(key("base_mint").as_str()));
+        assert_eq!(claim.signers(), [key("payer")]);
+        assert_eq!(claim.pool(), Some(key("pool").as_str()));
+        assert_eq!(claim.recipient(), (key("fee_share_authority").as_str(), key("fee_share_authority_quote_ata").as_str()));
+    }
+
+    #[test]
+    fn decodes_v1_claims_for_the_signing_claimer() {
+        let decoder = FeeShareDecoder::v1();
+
+        let claim = decoder.decode_claim(&CLAIM_B, &accounts(&CLAIM_B_ACCOUNTS)).unwrap();
+        assert_eq!(claim.kind(), "V1_CLAIM");
+        assert_eq!(claim.base_mint(), Some(key("base_mint").as_str()));
+        assert_eq!(claim.signers(), [key("claimer_b")]);
+        assert_eq!(claim.recipient(), (key("claimer_b").as_str(), key("vault_b").as_str()));
+        assert!(claim.is_claimer_withdrawal());
+
+        let claim = decoder.decode_claim(&CLAIM_DAMM_A, &accounts(&CLAIM_DAMM_A_ACCOUNTS)).unwrap();
+        assert_eq!(claim.signers(), [key("claimer_a")]);
+        assert_eq!(claim.recipient(), (key("claimer_a").as_str(), key("vault_a").as_str()));
+    }
+
+    #[test]
+    fn routes_programs_to_their_idl() {
+        let v2 = FeeShareDecoder::for_program("FEE2tBhCKAt7shrod19QttSVREUYPiyMzoku1mL1gqVK").unwrap();
+        let v1 = FeeShareDecoder::for_program("FEEhPbKVKnco9EXnaY3i4R5rQVUx91wgVfu8qokixywi").unwrap();
+        assert_eq!(v2.instruction_name(&CLAIM_USER), Some("claim_user"));
+        assert_eq!(v1.instruction_name(&CLAIM_B), Some("claim_b"));
+        // Each program only knows its own discriminators
+        assert!(v1.decode_claim(&CLAIM_DBC, &accounts(&CLAIM_DBC_ACCOUNTS)).is_none());
+        assert!(FeeShareDecoder::for_program("11111111111111111111111111111111").is_none());
+    }
+}
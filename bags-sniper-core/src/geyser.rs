@@ -7,9 +7,11 @@ use tonic::metadata::MetadataValue;
 use tonic::{Request, Streaming};
 use yellowstone_grpc_proto::geyser::{
     geyser_client::GeyserClient,
-    SubscribeRequest, SubscribeRequestFilterTransactions,
-    SubscribeUpdate, CommitmentLevel,
+    SubscribeRequest, SubscribeRequestFilterAccounts, SubscribeRequestFilterBlocksMeta, SubscribeRequestFilterTransactions,
+    SubscribeRequestFilterSlots, SubscribeRequestPing, SubscribeUpdate, CommitmentLevel,
 };
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
 use std::collections::HashMap;
 use anyhow::Result;
 use log::info;
@@ -69,10 +71,13 @@ impl GeyserConnection {
     }
 
     /// Subscribe to transactions involve specific programs
+    ///
+    /// Slot updates are included so a healthy stream is never silent.
+    /// The returned sender stays open for keepalive pings.
     pub async fn subscribe_programs(
         &mut self,
         program_ids: Vec<String>,
-    ) -> Result<Streaming<SubscribeUpdate>> {
+    ) -> Result<(mpsc::Sender<SubscribeRequest>, Streaming<SubscribeUpdate>)> {
         info!("Subscribing to programs: {:?}", program_ids);
         
         // Build subscription request
@@ -89,8 +94,16 @@ impl GeyserConnection {
             },
         );
 
+        let mut slots = HashMap::new();
+        slots.insert(
+            "slots".to_string(),
+            SubscribeRequestFilterSlots {
+                filter_by_commitment: Some(true),
+            },
+        );
+
         let request = SubscribeRequest {
-            slots: HashMap::new(),
+            slots,
             accounts: HashMap::new(),
             transactions,
             blocks: HashMap::new(),
@@ -102,12 +115,83 @@ impl GeyserConnection {
             transactions_status: HashMap::new(),
         };
 
-        let request_stream = tokio_stream::iter(std::iter::once(request));
-        let response = self.client.subscribe(request_stream).await?;
+        let (request_tx, request_rx) = mpsc::channel(16);
+        request_tx.send(request).await?;
+        let response = self.client.subscribe(ReceiverStream::new(request_rx)).await?;
         let stream = response.into_inner();
         
         info!("✅ Subscribed to transaction stream");
         
-        Ok(stream)
+        Ok((request_tx, stream))
+    }
+
+    /// Subscribe to lamport/data updates of specific accounts
+    ///
+    /// Send `accounts_request` on the returned sender to change the set.
+    pub async fn subscribe_accounts(
+        &mut self,
+        accounts: Vec<String>,
+    ) -> Result<(mpsc::Sender<SubscribeRequest>, Streaming<SubscribeUpdate>)> {
+        info!("Subscribing to {} account(s)", accounts.len());
+
+        let (request_tx, request_rx) = mpsc::channel(16);
+        request_tx.send(Self::accounts_request(accounts)).await?;
+        let response = self.client.subscribe(ReceiverStream::new(request_rx)).await?;
+        let stream = response.into_inner();
+
+        info!("✅ Subscribed to account stream");
+
+        Ok((request_tx, stream))
+    }
+
+    /// Account subscription request; replaces the active filters.
+    /// An empty list sends no account filter (an empty filter would match every account).
+    pub fn accounts_request(accounts: Vec<String>) -> SubscribeRequest {
+        let mut filters = HashMap::new();
+        if !accounts.is_empty() {
+            filters.insert(
+                "wallets".to_string(),
+                SubscribeRequestFilterAccounts {
+                    account: accounts,
+                    owner: vec![],
+                    filters: vec![],
+                },
+            );
+        }
+
+        SubscribeRequest {
+            accounts: filters,
+            commitment: Some(CommitmentLevel::Confirmed as i32),
+            ..Default::default()
+        }
+    }
+
+    /// Subscribe to confirmed block metadata (blockhash and block height)
+    pub async fn subscribe_blocks_meta(
+        &mut self,
+    ) -> Result<(mpsc::Sender<SubscribeRequest>, Streaming<SubscribeUpdate>)> {
+        let mut blocks_meta = HashMap::new();
+        blocks_meta.insert("blockhash".to_string(), SubscribeRequestFilterBlocksMeta {});
+
+        let (request_tx, request_rx) = mpsc::channel(16);
+        request_tx.send(SubscribeRequest {
+            blocks_meta,
+            commitment: Some(CommitmentLevel::Confirmed as i32),
+            ..Default::default()
+        }).await?;
+        let response = self.client.subscribe(ReceiverStream::new(request_rx)).await?;
+        let stream = response.into_inner();
+
+        info!("✅ Subscribed to block meta stream");
+
+        Ok((request_tx, stream))
+    }
+
+    /// Ping request; carries no filters so the active subscription is kept
+    pub fn ping_request(id: i32) -> SubscribeRequest {
+        SubscribeRequest {
+            ping: Some(SubscribeRequestPing { id }),
+            ..Default::default()
+        }
     }
 }
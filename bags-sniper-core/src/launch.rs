@@ -0,0 +1,155 @@
+//! Launch Detection for Bags Sniper
+//!
+//! Spots new Bags token launches in the Geyser stream: a Meteora DBC pool
+//! created in a transaction that carries the Bags token creation
+//! authority. Users' launch rules (creator allowlist, name/symbol regex,
+//! creator's initial buy size) decide which launches are added to their
+//! watchlist automatically.
+
+use crate::fee_share::{DecodedInstruction, FeeShareDecoder};
+use crate::supabase::LaunchRuleRow;
+use regex::{Regex, RegexBuilder};
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::OnceLock;
+use yellowstone_grpc_proto::prelude::CompiledInstruction;
+
+const DBC_IDL: &str = include_str!("../../bags-sdk-main/src/idl/dynamic-bonding-curve/idl.json");
+
+/// Signs (or is included in) every token launch made through Bags
+pub const BAGS_TOKEN_CREATION_AUTHORITY: &str = "BAGSB9TpGrZxQbEsrEznv5jXXdwyP6AXerN8aVRiAmcv";
+pub const WSOL_MINT: &str = "So11111111111111111111111111111111111111112";
+
+/// Decoder for the Meteora DBC IDL bundled at build time
+fn dbc_decoder() -> &'static FeeShareDecoder {
+    static DECODER: OnceLock<FeeShareDecoder> = OnceLock::new();
+    DECODER.get_or_init(|| {
+        FeeShareDecoder::from_idl(DBC_IDL).expect("Bundled dynamic-bonding-curve IDL is invalid")
+    })
+}
+
+/// A newly launched Bags token
+#[derive(Debug, Clone)]
+pub struct DetectedLaunch {
+    pub signature: String,
+    pub mint: String,
+    pub creator: String,
+    pub pool: String,
+    pub name: String,
+    pub symbol: String,
+    /// SOL the launch transaction swapped into the new pool, if any
+    pub initial_buy_sol: Option<f64>,
+}
+
+impl fmt::Display for DetectedLaunch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}) | Mint: {} | Creator: {} | Pool: {} | Initial buy: {}",
+            self.name,
+            self.symbol,
+            self.mint,
+            self.creator,
+            self.pool,
+            self.initial_buy_sol.map(|a| format!("{:.4} SOL", a)).unwrap_or_else(|| "none".to_string())
+        )
+    }
+}
+
+/// Find a Bags launch among a transaction's top-level instructions
+pub fn detect_launch(
+    signature: &str,
+    account_keys: &[Vec<u8>],
+    instructions: &[CompiledInstruction],
+) -> Option<DetectedLaunch> {
+    let authority = bs58::decode(BAGS_TOKEN_CREATION_AUTHORITY).into_vec().ok()?;
+    if !account_keys.contains(&authority) {
+        return None;
+    }
+
+    let decoder = dbc_decoder();
+    let decoded: Vec<DecodedInstruction> = instructions.iter()
+        .filter(|inst| {
+            account_keys.get(inst.program_id_index as usize)
+                .map(|k| bs58::encode(k).into_string() == decoder.program_id())
+                .unwrap_or(false)
+        })
+        .filter_map(|inst| {
+            let accounts: Vec<String> = inst.accounts.iter()
+                .filter_map(|&index| account_keys.get(index as usize))
+                .map(|k| bs58::encode(k).into_string())
+                .collect();
+            if accounts.len() != inst.accounts.len() {
+                return None;
+            }
+            decoder.decode_instruction(&inst.data, &accounts)
+        })
+        .collect();
+
+    let init = decoded.iter().find(|ix| ix.name.starts_with("initialize_virtual_pool"))?;
+    let pool = init.account("pool")?;
+
+    // A fresh pool can only be bought, so any swap on it is quote -> base
+    let initial_buy_lamports: u64 = decoded.iter()
+        .filter(|ix| ix.name == "swap" && ix.account("pool") == Some(pool) && ix.account("quote_mint") == Some(WSOL_MINT))
+        .filter_map(|ix| ix.u64_arg("amount_in"))
+        .sum();
+
+    Some(DetectedLaunch {
+        signature: signature.to_string(),
+        mint: init.account("base_mint")?.to_string(),
+        creator: init.account("creator")?.to_string(),
+        pool: pool.to_string(),
+        name: init.string_arg("name")?.to_string(),
+        symbol: init.string_arg("symbol")?.to_string(),
+        initial_buy_sol: (initial_buy_lamports > 0).then(|| initial_buy_lamports as f64 / 1e9),
+    })
+}
+
+/// A launch rule with its patterns compiled
+#[derive(Debug, Clone)]
+pub struct LaunchRule {
+    pub row: LaunchRuleRow,
+    creators: HashSet<String>,
+    name_pattern: Option<Regex>,
+    symbol_pattern: Option<Regex>,
+}
+
+impl LaunchRule {
+    pub fn compile(row: LaunchRuleRow) -> Result<Self, regex::Error> {
+        let pattern = |p: &Option<String>| -> Result<Option<Regex>, regex::Error> {
+            match p.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+                Some(p) => Ok(Some(RegexBuilder::new(p).case_insensitive(true).build()?)),
+                None => Ok(None),
+            }
+        };
+        Ok(Self {
+            creators: row.creator_allowlist.iter().cloned().collect(),
+            name_pattern: pattern(&row.name_pattern)?,
+            symbol_pattern: pattern(&row.symbol_pattern)?,
+            row,
+        })
+    }
+
+    /// Every set criterion must match. A launch with no initial buy
+    /// fails a minimum initial buy.
+    pub fn matches(&self, launch: &DetectedLaunch) -> bool {
+        if !self.creators.is_empty() && !self.creators.contains(&launch.creator) {
+            return false;
+        }
+        if self.name_pattern.as_ref().is_some_and(|re| !re.is_match(&launch.name)) {
+            return false;
+        }
+        if self.symbol_pattern.as_ref().is_some_and(|re| !re.is_match(&launch.symbol)) {
+            return false;
+        }
+        let initial_buy = launch.initial_buy_sol.unwrap_or(0.0);
+        if self.row.min_initial_buy.is_some_and(|min| initial_buy < min) {
+            return false;
+        }
+        if self.row.max_initial_buy.is_some_and(|max| initial_buy > max) {
+            return false;
+        }
+        true
+    }
+}
@@ -0,0 +1,299 @@
+//! Snipe Lifecycle for Bags Sniper
+//!
+//! Tracks where each (user, mint) snipe stands:
+//! watching -> claim_detected -> tx_sent -> confirmed | failed.
+//! Every transition is timestamped and written to the `snipes` table in
+//! order, so the dashboard can show the live state of every CA. The
+//! commitment of the current buy (processed, confirmed, finalized or
+//! expired) is written alongside as the confirmation tracker reports it.
+
+use crate::confirm::TxStatus;
+use crate::supabase::{SnipeRow, SupabaseClient};
+use chrono::{DateTime, Utc};
+use log::{error, warn};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnipeState {
+    Watching,
+    ClaimDetected,
+    TxSent,
+    Confirmed,
+    Failed,
+}
+
+impl SnipeState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SnipeState::Watching => "watching",
+            SnipeState::ClaimDetected => "claim_detected",
+            SnipeState::TxSent => "tx_sent",
+            SnipeState::Confirmed => "confirmed",
+            SnipeState::Failed => "failed",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "watching" => Some(SnipeState::Watching),
+            "claim_detected" => Some(SnipeState::ClaimDetected),
+            "tx_sent" => Some(SnipeState::TxSent),
+            "confirmed" => Some(SnipeState::Confirmed),
+            "failed" => Some(SnipeState::Failed),
+            _ => None,
+        }
+    }
+
+    /// Whether `next` may follow this state. Retries send again with a
+    /// new signature; finished snipes can start a new cycle when a repeat
+    /// trigger mode fires again.
+    fn allows(&self, next: SnipeState) -> bool {
+        use SnipeState::*;
+        matches!(
+            (self, next),
+            (Watching, ClaimDetected)
+                | (ClaimDetected, TxSent)
+                | (ClaimDetected, Failed)
+                | (TxSent, TxSent)
+                | (TxSent, Confirmed)
+                | (TxSent, Failed)
+                | (Confirmed, ClaimDetected)
+                | (Failed, ClaimDetected)
+        )
+    }
+}
+
+impl fmt::Display for SnipeState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One state change, as written to Supabase
+#[derive(Debug, Clone)]
+pub struct SnipeTransition {
+    pub wallet_address: String,
+    pub mint_address: String,
+    pub state: SnipeState,
+    pub at: DateTime<Utc>,
+    pub claim_signature: Option<String>,
+    pub buy_signature: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Commitment reached by a buy, as written to Supabase
+#[derive(Debug, Clone)]
+pub struct SnipeCommitment {
+    pub wallet_address: String,
+    pub mint_address: String,
+    pub buy_signature: String,
+    pub status: TxStatus,
+    pub at: DateTime<Utc>,
+}
+
+enum SnipeWrite {
+    Transition(SnipeTransition),
+    Commitment(SnipeCommitment),
+}
+
+#[derive(Clone)]
+pub struct SnipeLifecycle {
+    /// (user_id:mint) -> current state
+    states: Arc<Mutex<HashMap<String, SnipeState>>>,
+    writes: mpsc::UnboundedSender<SnipeWrite>,
+}
+
+impl SnipeLifecycle {
+    /// Create the tracker and spawn its ordered Supabase writer
+    pub fn new(supabase: Arc<SupabaseClient>) -> Self {
+        let (writes, rx) = mpsc::unbounded_channel();
+        tokio::spawn(write_transitions(supabase, rx));
+        Self {
+            states: Arc::new(Mutex::new(HashMap::new())),
+            writes,
+        }
+    }
+
+    /// Restore persisted states so a restart doesn't reset finished snipes to watching
+    pub fn seed(&self, rows: &[SnipeRow]) {
+        let mut states = self.states.lock().unwrap();
+        for row in rows {
+            if let Some(state) = SnipeState::parse(&row.state) {
+                states.insert(format!("{}:{}", row.wallet_address, row.mint_address), state);
+            }
+        }
+    }
+
+    /// Treat a bought snipe with no persisted state as confirmed (not written)
+    pub fn seed_bought(&self, user_id: &str, mint: &str) {
+        self.states.lock().unwrap()
+            .entry(format!("{}:{}", user_id, mint))
+            .or_insert(SnipeState::Confirmed);
+    }
+
+    /// A mint is now watched; only recorded if it has no state yet
+    pub fn watch(&self, user_id: &str, mint: &str) {
+        let key = format!("{}:{}", user_id, mint);
+        let mut states = self.states.lock().unwrap();
+        if states.contains_key(&key) {
+            return;
+        }
+        states.insert(key, SnipeState::Watching);
+        drop(states);
+        self.write(user_id, mint, SnipeState::Watching, None, None, None);
+    }
+
+    pub fn claim_detected(&self, user_id: &str, mint: &str, claim_signature: &str) {
+        self.transition(user_id, mint, SnipeState::ClaimDetected, Some(claim_signature), None, None);
+    }
+
+    pub fn tx_sent(&self, user_id: &str, mint: &str, buy_signature: &str) {
+        self.transition(user_id, mint, SnipeState::TxSent, None, Some(buy_signature), None);
+    }
+
+    pub fn confirmed(&self, user_id: &str, mint: &str, buy_signature: &str) {
+        self.transition(user_id, mint, SnipeState::Confirmed, None, Some(buy_signature), None);
+    }
+
+    pub fn failed(&self, user_id: &str, mint: &str, error: &str) {
+        self.transition(user_id, mint, SnipeState::Failed, None, None, Some(error));
+    }
+
+    /// Record the commitment a buy reached; only touches the row while
+    /// `buy_signature` is still its current buy
+    pub fn commitment(&self, user_id: &str, mint: &str, buy_signature: &str, status: &TxStatus) {
+        if *status == TxStatus::Pending {
+            return;
+        }
+        let _ = self.writes.send(SnipeWrite::Commitment(SnipeCommitment {
+            wallet_address: user_id.to_string(),
+            mint_address: mint.to_string(),
+            buy_signature: buy_signature.to_string(),
+            status: status.clone(),
+            at: Utc::now(),
+        }));
+    }
+
+    fn transition(
+        &self,
+        user_id: &str,
+        mint: &str,
+        next: SnipeState,
+        claim_signature: Option<&str>,
+        buy_signature: Option<&str>,
+        error: Option<&str>,
+    ) {
+        let key = format!("{}:{}", user_id, mint);
+        {
+            let mut states = self.states.lock().unwrap();
+            let current = *states.get(&key).unwrap_or(&SnipeState::Watching);
+            if !current.allows(next) {
+                warn!("⚠️ Ignoring snipe transition {} -> {} for user {} mint {}", current, next, user_id, mint);
+                return;
+            }
+            states.insert(key, next);
+        }
+        self.write(user_id, mint, next, claim_signature, buy_signature, error);
+    }
+
+    fn write(
+        &self,
+        user_id: &str,
+        mint: &str,
+        state: SnipeState,
+        claim_signature: Option<&str>,
+        buy_signature: Option<&str>,
+        error: Option<&str>,
+    ) {
+        let _ = self.writes.send(SnipeWrite::Transition(SnipeTransition {
+            wallet_address: user_id.to_string(),
+            mint_address: mint.to_string(),
+            state,
+            at: Utc::now(),
+            claim_signature: claim_signature.map(str::to_string),
+            buy_signature: buy_signature.map(str::to_string),
+            error: error.map(str::to_string),
+        }));
+    }
+}
+
+/// Write transitions one at a time so Supabase sees them in order
+async fn write_transitions(supabase: Arc<SupabaseClient>, mut rx: mpsc::UnboundedReceiver<SnipeWrite>) {
+    while let Some(write) = rx.recv().await {
+        match write {
+            SnipeWrite::Transition(transition) => {
+                if let Err(e) = supabase.upsert_snipe(&transition).await {
+                    error!(
+                        "❌ Failed to record snipe {} for user {} mint {}: {}",
+                        transition.state, transition.wallet_address, transition.mint_address, e
+                    );
+                }
+            }
+            SnipeWrite::Commitment(commitment) => {
+                if let Err(e) = supabase.update_snipe_commitment(&commitment).await {
+                    error!(
+                        "❌ Failed to record {} buy for user {} mint {}: {}",
+                        commitment.status.as_str(), commitment.wallet_address, commitment.mint_address, e
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use SnipeState::*;
+
+    const ALL: [SnipeState; 5] = [Watching, ClaimDetected, TxSent, Confirmed, Failed];
+
+    #[test]
+    fn happy_path_and_retries_are_allowed() {
+        assert!(Watching.allows(ClaimDetected));
+        assert!(ClaimDetected.allows(TxSent));
+        // A retry sends again with a new signature
+        assert!(TxSent.allows(TxSent));
+        assert!(TxSent.allows(Confirmed));
+    }
+
+    #[test]
+    fn failures_and_new_cycles_are_allowed() {
+        assert!(ClaimDetected.allows(Failed));
+        assert!(TxSent.allows(Failed));
+        assert!(Confirmed.allows(ClaimDetected));
+        assert!(Failed.allows(ClaimDetected));
+    }
+
+    #[test]
+    fn skipping_or_reversing_steps_is_rejected() {
+        assert!(!Watching.allows(TxSent));
+        assert!(!Watching.allows(Confirmed));
+        assert!(!ClaimDetected.allows(Confirmed));
+        assert!(!Confirmed.allows(Failed));
+        assert!(!Failed.allows(Confirmed));
+        assert!(!Confirmed.allows(TxSent));
+        assert!(!Failed.allows(TxSent));
+        for state in ALL {
+            assert!(!state.allows(Watching), "{} -> watching", state);
+        }
+    }
+
+    #[test]
+    fn only_tx_sent_may_repeat() {
+        for state in ALL {
+            assert_eq!(state.allows(state), state == TxSent, "{} -> {}", state, state);
+        }
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for state in ALL {
+            assert_eq!(SnipeState::parse(state.as_str()), Some(state));
+        }
+        assert_eq!(SnipeState::parse("sniped"), None);
+    }
+}
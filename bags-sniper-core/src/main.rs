@@ -7,18 +7,34 @@ mod sniper;
 mod manager;
 mod telegram;
 mod encryption;
+mod multiplexer;
+mod backfill;
+mod fee_share;
+mod trigger;
+mod sync;
+mod budget;
+mod balance;
+mod lifecycle;
+mod launch;
+mod risk;
+mod retry;
+mod broadcast;
+mod confirm;
+mod blockhash;
 
 use crate::supabase::SupabaseClient;
 use crate::jupiter::JupiterClient;
-use crate::geyser::GeyserConnection;
+use crate::multiplexer::{GeyserEndpoint, StreamConfig, StreamMultiplexer};
 use crate::sniper::Sniper;
 use crate::manager::SniperManager;
+use crate::backfill::Backfiller;
+use crate::launch::BAGS_TOKEN_CREATION_AUTHORITY;
+use crate::sync::refresh_users;
 use dotenv::dotenv;
 use log::{error, info};
 use std::env;
 use std::sync::Arc;
 use std::time::Duration;
-use futures::StreamExt;
 
 /// Bags Fee Share V2 Program ID
 const BAGS_V2_PROGRAM_ID: &str = "FEE2tBhCKAt7shrod19QttSVREUYPiyMzoku1mL1gqVK";
@@ -34,8 +50,6 @@ async fn main() -> anyhow::Result<()> {
 
     // Load config
     let rpc_url = env::var("RPC_URL").expect("RPC_URL must be set");
-    let grpc_url = env::var("GRPC_URL").expect("GRPC_URL must be set");
-    let grpc_token = env::var("GRPC_X_TOKEN").ok();
     
     let supabase_url = env::var("SUPABASE_URL")
         .or_else(|_| env::var("NEXT_PUBLIC_SUPABASE_URL"))
@@ -46,18 +60,34 @@ async fn main() -> anyhow::Result<()> {
     // Initialize clients
     let supabase = Arc::new(SupabaseClient::new(supabase_url, supabase_key));
     let jupiter = Arc::new(JupiterClient::new(rpc_url.clone()));
-    let manager = Arc::new(SniperManager::new(rpc_url, supabase.clone(), Some(jupiter.clone())));
+    let manager = Arc::new(SniperManager::new(rpc_url.clone(), supabase.clone(), Some(jupiter.clone())));
     let sniper = Sniper::new(manager.clone());
 
+    // Restore which mints were already bought, before any claim can fire
+    let snipes = supabase.get_snipes().await?;
+    manager.seed_snipes(&snipes);
+    let buys = supabase.get_buy_history().await?;
+    let sniped = supabase.get_sniped_watchlist().await?;
+    let seeded = manager.seed_history(&buys, &sniped);
+    info!("🔒 Restored buy history for {} watchlist item(s)", seeded);
+
     // Load initial users
     info!("📦 Loading active users...");
     refresh_users(&supabase, &manager).await?;
 
-    // Spawn user refresh task (every 1 second for "instant" updates)
+    // Spawn realtime task: apply watchlist/settings/status changes as they happen
+    tokio::spawn(sync::run_realtime(supabase.clone(), manager.clone()));
+
+    // Spawn reconciliation task: slow full refresh as a safety net
+    let reconcile_secs: u64 = env::var("USER_RECONCILE_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(60);
     let supabase_clone = supabase.clone();
     let manager_clone = manager.clone();
     tokio::spawn(async move {
-        let mut interval = tokio::time::interval(Duration::from_secs(1));
+        let mut interval = tokio::time::interval(Duration::from_secs(reconcile_secs));
+        interval.tick().await;
         loop {
             interval.tick().await;
             if let Err(e) = refresh_users(&supabase_clone, &manager_clone).await {
@@ -76,110 +106,84 @@ async fn main() -> anyhow::Result<()> {
         }
     });
 
-    // gRPC Connection with Auto-Reconnect
-    let mut backoff_secs = 5u64;
-    const MAX_BACKOFF_SECS: u64 = 60;
-
-    loop {
-        info!("🔌 Connecting to Yellowstone gRPC...");
-        
-        let geyser_result = GeyserConnection::connect(
-            grpc_url.clone(), 
-            grpc_token.clone(), 
-            None
-        ).await;
-
-        let mut geyser = match geyser_result {
-            Ok(g) => {
-                backoff_secs = 5; // Reset backoff on successful connect
-                g
-            }
-            Err(e) => {
-                error!("❌ gRPC Connection Failed: {}. Retrying in {}s...", e, backoff_secs);
-                tokio::time::sleep(Duration::from_secs(backoff_secs)).await;
-                backoff_secs = (backoff_secs * 2).min(MAX_BACKOFF_SECS);
-                continue;
-            }
-        };
-
-        let stream_result = geyser.subscribe_programs(vec![
+    // Race every configured gRPC provider, first copy of each transaction wins
+    let endpoints = GeyserEndpoint::from_env();
+    info!("📡 Racing {} Yellowstone gRPC endpoint(s)", endpoints.len());
+    let multiplexer = StreamMultiplexer::new(endpoints.clone(), StreamConfig::from_env());
+    let mut updates = multiplexer.spawn(
+        vec![
             BAGS_V2_PROGRAM_ID.to_string(),
             BAGS_V1_PROGRAM_ID.to_string(),
-        ]).await;
-
-        let mut stream = match stream_result {
-            Ok(s) => s,
-            Err(e) => {
-                error!("❌ gRPC Subscription Failed: {}. Retrying in {}s...", e, backoff_secs);
-                tokio::time::sleep(Duration::from_secs(backoff_secs)).await;
-                backoff_secs = (backoff_secs * 2).min(MAX_BACKOFF_SECS);
-                continue;
-            }
-        };
-
-        info!("👂 Listening for claim events on-chain...");
-        manager.broadcast_log("INFO", "Connected to gRPC stream. Monitoring for claims...").await;
-
-        while let Some(message) = stream.next().await {
-            match message {
-                Ok(update) => {
-                    let sniper_clone = sniper.clone();
-                    tokio::spawn(async move {
-                        sniper_clone.process_update(update).await;
-                    });
-                }
-                Err(e) => {
-                    error!("❌ gRPC Stream Error: {}. Reconnecting in {}s...", e, backoff_secs);
-                    manager.broadcast_log("ERROR", &format!("Stream disconnected: {}. Reconnecting...", e)).await;
-                    break; // Break inner loop to reconnect
-                }
-            }
-        }
+            BAGS_TOKEN_CREATION_AUTHORITY.to_string(),
+        ],
+        manager.clone(),
+    );
+
+    let broadcaster = manager.broadcaster();
+    info!("📤 Sending buys through {} endpoint(s)", broadcaster.endpoint_count());
 
-        // Stream ended or errored - wait before reconnecting
-        tokio::time::sleep(Duration::from_secs(backoff_secs)).await;
-        backoff_secs = (backoff_secs * 2).min(MAX_BACKOFF_SECS);
+    // Spawn confirmation task: follow sent buys until they settle
+    tokio::spawn(manager.tracker().run());
+
+    // Spawn balance task: follow trading wallet balances on the primary endpoint
+    if let Some(primary) = endpoints.first() {
+        tokio::spawn(manager.balances().run(primary.clone(), StreamConfig::from_env()));
     }
-}
 
-async fn refresh_users(supabase: &SupabaseClient, manager: &SniperManager) -> anyhow::Result<()> {
-    let active_users = supabase.get_active_users().await?;
-    let encryption_key = env::var("ENCRYPTION_KEY").unwrap_or_else(|_| "22e83f82f9bacb57ccb213476ff60db9de7be2e8d0d41cf1e6704265ccc626fd".to_string());
-    
-    for user in active_users {
-        let (watchlist, settings, pk) = tokio::join!(
-            supabase.get_user_watchlist(&user.wallet_address),
-            supabase.get_user_settings(&user.wallet_address),
-            supabase.get_user_private_key(&user.wallet_address)
-        );
-        
-        match &pk {
-            Ok(Some(stored_key)) => {
-                // Decrypt the private key if encrypted
-                let private_key = match encryption::decrypt_private_key(stored_key, &encryption_key) {
-                    Ok(decrypted) => decrypted,
-                    Err(e) => {
-                        error!("❌ Failed to decrypt private key for {}: {}", &user.wallet_address[..8], e);
-                        continue;
-                    }
-                };
-                
-                let settings = settings.unwrap_or_default();
-                manager.register_user(user.wallet_address.clone(), private_key, settings);
-                
-                if let Ok(items) = watchlist {
-                    for item in items {
-                        manager.add_to_watchlist(&user.wallet_address, item.mint_address, item.buy_amount).ok();
-                    }
-                }
-            },
-            Ok(None) => {
-                // User has no private key, skip
-            },
-            Err(e) => {
-                error!("❌ Error fetching private key for {}: {}", &user.wallet_address[..8], e);
-            }
+    // Spawn blockhash tasks: block meta on the primary endpoint, RPC polling as a fallback
+    let blockhash = manager.blockhash();
+    if let Some(primary) = endpoints.first() {
+        tokio::spawn(blockhash.clone().run_geyser(primary.clone(), StreamConfig::from_env()));
+    }
+    let blockhash_refresh_ms: u64 = env::var("BLOCKHASH_REFRESH_MS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(2_000);
+    tokio::spawn(blockhash.clone().run_rpc(Duration::from_millis(blockhash_refresh_ms)));
+
+    // Spawn risk task: refresh pre-trade risk reports of watched mints
+    let risk_refresh_secs: u64 = env::var("RISK_REFRESH_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(300);
+    let manager_risk = manager.clone();
+    tokio::spawn(manager.risk().run(Duration::from_secs(risk_refresh_secs), move |mint| manager_risk.is_watched_mint(mint)));
+
+    // Spawn backfill task: replay claims from slots every provider missed
+    let backfiller = Backfiller::new(
+        rpc_url,
+        sniper.clone(),
+        multiplexer.clone(),
+        &[BAGS_V2_PROGRAM_ID, BAGS_V1_PROGRAM_ID],
+    );
+    let gaps = multiplexer.subscribe_gaps();
+    tokio::spawn(backfiller.run(gaps));
+
+    // Spawn provider and matching stats logging task (every 60 seconds)
+    let multiplexer_stats = multiplexer.clone();
+    let manager_stats = manager.clone();
+    let broadcaster_stats = broadcaster.clone();
+    let blockhash_stats = blockhash.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(60));
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            multiplexer_stats.log_stats();
+            manager_stats.log_match_stats();
+            broadcaster_stats.log_stats();
+            blockhash_stats.log_stats();
+        }
+    });
+
+    while let Some(raced) = updates.recv().await {
+        if let Some(update) = multiplexer.accept(raced) {
+            let sniper_clone = sniper.clone();
+            tokio::spawn(async move {
+                sniper_clone.process_update(update).await;
+            });
         }
     }
+
     Ok(())
 }
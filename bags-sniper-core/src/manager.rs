@@ -1,28 +1,253 @@
-use crate::executor::TransactionExecutor;
+use crate::budget::{SpendLedger, SpendLimits};
+use crate::balance::{buy_overhead_lamports, BalanceTracker};
+use crate::blockhash::BlockhashCache;
+use crate::broadcast::{Broadcaster, SendEndpoint};
+use crate::confirm::{ConfirmationTracker, TxStatus};
+use crate::risk::{RiskChecker, RiskPolicy};
+use crate::executor::{self, AttemptEvent, BuyOrder, ConfirmedBuy, TransactionExecutor};
+use crate::lifecycle::SnipeLifecycle;
+use crate::supabase::{CreatorWatchItem, LaunchRuleRow, SnipeRecord, SnipeRow, TriggerPolicy, UserSettings, WatchlistItem};
+use crate::trigger::{check_claim_size, check_trigger_policy, BuySizing, TriggerRule, TriggerState};
+use crate::launch::{DetectedLaunch, LaunchRule};
+use crate::sniper::{ClaimSource, DetectedClaim};
 use crate::telegram::TelegramNotifier;
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 use log::{info, error};
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use std::collections::HashSet;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+/// Per-mint watch configuration
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchEntry {
+    pub buy_amount: f64, // SOL (fixed sizing)
+    pub sizing: BuySizing,
+    pub trigger_policy: TriggerPolicy,
+    pub min_claim_amount: f64, // SOL (or quote token)
+    pub trigger_rule: TriggerRule,
+}
+
+impl From<&LaunchRuleRow> for WatchEntry {
+    fn from(rule: &LaunchRuleRow) -> Self {
+        Self {
+            buy_amount: rule.buy_amount,
+            sizing: BuySizing::new(rule.buy_mode, rule.buy_amount, rule.buy_percent, &rule.buy_tiers),
+            trigger_policy: rule.trigger_policy,
+            min_claim_amount: rule.min_claim_amount,
+            trigger_rule: TriggerRule {
+                mode: rule.trigger_mode,
+                cooldown: Duration::from_secs(rule.cooldown_secs),
+                every_n: rule.trigger_every_n,
+                max_buys: rule.max_buys,
+            },
+        }
+    }
+}
+
+impl From<&CreatorWatchItem> for WatchEntry {
+    fn from(item: &CreatorWatchItem) -> Self {
+        Self {
+            buy_amount: item.buy_amount,
+            sizing: BuySizing::new(item.buy_mode, item.buy_amount, item.buy_percent, &item.buy_tiers),
+            // The claim is signed by the creator by definition
+            trigger_policy: TriggerPolicy::Any,
+            min_claim_amount: item.min_claim_amount,
+            trigger_rule: TriggerRule {
+                mode: item.trigger_mode,
+                cooldown: Duration::from_secs(item.cooldown_secs),
+                every_n: item.trigger_every_n,
+                max_buys: item.max_buys,
+            },
+        }
+    }
+}
+
+impl From<&WatchlistItem> for WatchEntry {
+    fn from(item: &WatchlistItem) -> Self {
+        Self {
+            buy_amount: item.buy_amount,
+            sizing: BuySizing::new(item.buy_mode, item.buy_amount, item.buy_percent, &item.buy_tiers),
+            trigger_policy: item.trigger_policy,
+            min_claim_amount: item.min_claim_amount,
+            trigger_rule: TriggerRule {
+                mode: item.trigger_mode,
+                cooldown: Duration::from_secs(item.cooldown_secs),
+                every_n: item.trigger_every_n,
+                max_buys: item.max_buys,
+            },
+        }
+    }
+}
+
+/// Cached creator lookup for a mint
+#[derive(Debug, Clone)]
+enum CreatorLookup {
+    Pending,
+    Known(String),
+    Failed(Instant),
+}
+
+impl CreatorLookup {
+    fn known(&self) -> Option<&String> {
+        match self {
+            CreatorLookup::Known(creator) => Some(creator),
+            _ => None,
+        }
+    }
+}
+
+/// How long a failed creator lookup is cached before retrying
+const CREATOR_RETRY: Duration = Duration::from_secs(60);
+
+/// A triggered buy waiting to be executed
+struct PendingBuy {
+    uid: String,
+    mint: String,
+    executor: TransactionExecutor,
+    private_key: String,
+    amount: f64,
+    /// How `amount` was sized, recorded with the trade
+    sizing: String,
+    slippage_bps: u64,
+    priority_fee_lamports: u64,
+    tip_lamports: u64,
+    deadline: Duration,
+    telegram_id: Option<String>,
+    /// Spend ledger reservation, released if the buy fails
+    reservation: u64,
+    /// (wallet, hold id) in the balance tracker
+    balance_hold: Option<(String, u64)>,
+}
+
+/// Mirror a sent buy's commitment into its snipe record until it settles.
+/// Failures, including a buy dropped after it was processed, are left to
+/// the retry loop, which only books a buy once it is confirmed.
+async fn follow_status(
+    lifecycle: SnipeLifecycle,
+    uid: String,
+    mint: String,
+    signature: String,
+    mut status: watch::Receiver<TxStatus>,
+) {
+    let mut confirmed = false;
+    loop {
+        let current = status.borrow_and_update().clone();
+        lifecycle.commitment(&uid, &mint, &signature, &current);
+        if matches!(current, TxStatus::Confirmed { .. } | TxStatus::Finalized { .. }) && !confirmed {
+            confirmed = true;
+            lifecycle.confirmed(&uid, &mint, &signature);
+        }
+        if current.is_final() || status.changed().await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Log and index the difference between a user's old and new watch
+/// entries (keyed by mint or creator); returns the added keys
+fn diff_entries(
+    user_id: &str,
+    label: &str,
+    old: &HashMap<String, WatchEntry>,
+    new: &HashMap<String, WatchEntry>,
+    index: &mut HashMap<String, HashSet<String>>,
+) -> Vec<String> {
+    for key in old.keys() {
+        if !new.contains_key(key) {
+            info!("User {} removed {} from {}", user_id, key, label);
+            unindex_watcher(index, user_id, key);
+        }
+    }
+    let mut added = Vec::new();
+    for (key, entry) in new {
+        match old.get(key) {
+            None => {
+                info!("User {} added {} to {} ({} SOL, trigger: {:?})", user_id, key, label, entry.buy_amount, entry.trigger_policy);
+                index.entry(key.clone()).or_default().insert(user_id.to_string());
+                added.push(key.clone());
+            },
+            Some(previous) if previous != entry => info!("User {} updated {} in {} ({} SOL, trigger: {:?})", user_id, key, label, entry.buy_amount, entry.trigger_policy),
+            Some(_) => {}
+        }
+    }
+    added
+}
+
+/// Drop one user from a mint's (or creator's) watcher set
+fn unindex_watcher(index: &mut HashMap<String, HashSet<String>>, user_id: &str, mint: &str) {
+    if let Some(uids) = index.get_mut(mint) {
+        uids.remove(user_id);
+        if uids.is_empty() {
+            index.remove(mint);
+        }
+    }
+}
+
+/// Per-claim matching cost, from mint resolution to the trigger decisions
+#[derive(Default)]
+pub struct MatchStats {
+    claims: AtomicU64,
+    /// (user, mint) watchers evaluated
+    candidates: AtomicU64,
+    total_nanos: AtomicU64,
+    max_nanos: AtomicU64,
+}
+
+impl MatchStats {
+    fn record(&self, candidates: usize, elapsed: Duration) {
+        let nanos = elapsed.as_nanos() as u64;
+        self.claims.fetch_add(1, Ordering::Relaxed);
+        self.candidates.fetch_add(candidates as u64, Ordering::Relaxed);
+        self.total_nanos.fetch_add(nanos, Ordering::Relaxed);
+        self.max_nanos.fetch_max(nanos, Ordering::Relaxed);
+    }
+}
 
 pub struct UserSniper {
     pub user_id: String,
-    pub watchlist: HashMap<String, f64>, // mint -> buy_amount (SOL)
-    pub creators: HashMap<String, String>, // mint -> creator_address
+    pub watchlist: HashMap<String, WatchEntry>, // mint -> watch config
+    pub creator_watchlist: HashMap<String, WatchEntry>, // creator wallet -> watch config
+    pub launch_rules: Vec<LaunchRule>,
     pub settings: crate::supabase::UserSettings,
     pub executor: crate::executor::TransactionExecutor,
     pub private_key: String,
+    /// Trading wallet derived from the private key
+    pub wallet: Option<String>,
 }
 
 pub struct SniperManager {
     // Map user_id (wallet address) -> UserSniper
-    users: Arc<Mutex<HashMap<String, UserSniper>>>,
+    users: Arc<RwLock<HashMap<String, UserSniper>>>,
+    // Map mint -> user ids watching it, so claims only touch their watchers
+    mint_index: Arc<RwLock<HashMap<String, HashSet<String>>>>,
+    // Map creator wallet -> user ids watching it
+    creator_index: Arc<RwLock<HashMap<String, HashSet<String>>>>,
+    // Map mint -> creator, for mints reached through the creator watchlist
+    creator_mints: Arc<Mutex<HashMap<String, String>>>,
+    match_stats: Arc<MatchStats>,
     // Map vault/config account -> mint (for Strategy B)
     vault_to_mint: Arc<Mutex<HashMap<String, String>>>,
-    // Idempotency: set of (user_id:mint) that were already sniped
-    sniped: Arc<Mutex<HashSet<String>>>,
-    rpc_url: String,
+    // Trigger counters per (user_id:mint), kept across user re-registration
+    triggers: Arc<Mutex<HashMap<String, TriggerState>>>,
+    // Spend ledger per user_id, checked and reserved before each buy
+    budgets: Arc<Mutex<HashMap<String, SpendLedger>>>,
+    // SOL balance and in-flight holds per trading wallet
+    balances: BalanceTracker,
+    risk: RiskChecker,
+    // Send endpoints and confirmation tracking shared by every user's executor
+    broadcaster: Broadcaster,
+    tracker: ConfirmationTracker,
+    blockhash: BlockhashCache,
+    // Per (user, mint) snipe state, mirrored to Supabase
+    lifecycle: SnipeLifecycle,
+    // Map mint -> creator lookup, shared by every user watching it
+    creators: Arc<Mutex<HashMap<String, CreatorLookup>>>,
+    // Mints whose fee share config PDAs are already in vault_to_mint
+    indexed_mints: Arc<Mutex<HashSet<String>>>,
+    metadata_client: Arc<solana_client::rpc_client::RpcClient>,
     supabase: Arc<crate::supabase::SupabaseClient>,
     jupiter: Option<Arc<crate::jupiter::JupiterClient>>,
     telegram: Arc<TelegramNotifier>,
@@ -30,196 +255,688 @@ pub struct SniperManager {
 
 impl SniperManager {
     pub fn new(rpc_url: String, supabase: Arc<crate::supabase::SupabaseClient>, jupiter: Option<Arc<crate::jupiter::JupiterClient>>) -> Self {
+        let broadcaster = Broadcaster::new(SendEndpoint::from_env(&rpc_url));
         Self {
-            users: Arc::new(Mutex::new(HashMap::new())),
+            users: Arc::new(RwLock::new(HashMap::new())),
+            mint_index: Arc::new(RwLock::new(HashMap::new())),
+            creator_index: Arc::new(RwLock::new(HashMap::new())),
+            creator_mints: Arc::new(Mutex::new(HashMap::new())),
+            match_stats: Arc::new(MatchStats::default()),
             vault_to_mint: Arc::new(Mutex::new(HashMap::new())),
-            sniped: Arc::new(Mutex::new(HashSet::new())),
-            rpc_url,
+            triggers: Arc::new(Mutex::new(HashMap::new())),
+            budgets: Arc::new(Mutex::new(HashMap::new())),
+            balances: BalanceTracker::new(rpc_url.clone()),
+            risk: RiskChecker::new(rpc_url.clone()),
+            tracker: ConfirmationTracker::new(rpc_url.clone(), broadcaster.clone()),
+            broadcaster,
+            blockhash: BlockhashCache::new(rpc_url.clone()),
+            lifecycle: SnipeLifecycle::new(supabase.clone()),
+            creators: Arc::new(Mutex::new(HashMap::new())),
+            indexed_mints: Arc::new(Mutex::new(HashSet::new())),
+            metadata_client: Arc::new(solana_client::rpc_client::RpcClient::new(rpc_url)),
             supabase,
             jupiter,
             telegram: Arc::new(TelegramNotifier::new()),
         }
     }
 
-    pub fn register_user(&self, user_id: String, private_key: String, settings: crate::supabase::UserSettings) {
-        let mut users = self.users.lock().unwrap();
-        let executor = TransactionExecutor::new(self.rpc_url.clone(), false); 
-        let user_sniper = UserSniper {
-            user_id: user_id.clone(),
-            watchlist: HashMap::new(),
-            creators: HashMap::new(),
-            settings,
-            private_key: private_key.clone(),
-            executor,
-        };
-        users.insert(user_id.clone(), user_sniper);
-        info!("Registered user: {}", user_id);
+    /// Create or update a user from the latest Supabase state.
+    /// Only changed settings and watchlist entries are touched: the
+    /// executor and unchanged entries are kept, and the new watchlists are
+    /// swapped in under one lock so they are never briefly empty.
+    pub fn sync_user(
+        &self,
+        user_id: &str,
+        private_key: String,
+        settings: UserSettings,
+        watchlist: HashMap<String, WatchEntry>,
+        creator_watchlist: HashMap<String, WatchEntry>,
+    ) {
+        let mut users = self.users.write().unwrap();
+        let user = users.entry(user_id.to_string()).or_insert_with(|| {
+            info!("Registered user: {}", user_id);
+            UserSniper {
+                user_id: user_id.to_string(),
+                watchlist: HashMap::new(),
+                creator_watchlist: HashMap::new(),
+                launch_rules: Vec::new(),
+                settings: settings.clone(),
+                private_key: String::new(),
+                executor: TransactionExecutor::new(
+                    false,
+                    self.broadcaster.clone(),
+                    self.tracker.clone(),
+                    self.blockhash.clone(),
+                ),
+                wallet: None,
+            }
+        });
+
+        if user.settings != settings {
+            info!("User {} settings updated", user_id);
+            user.settings = settings;
+        }
+        if user.private_key != private_key {
+            if !user.private_key.is_empty() {
+                info!("User {} wallet key updated", user_id);
+            }
+            if let Some(old) = user.wallet.take() {
+                self.balances.untrack(&old);
+            }
+            user.wallet = executor::wallet_address(&private_key);
+            match &user.wallet {
+                Some(wallet) => self.balances.track(wallet),
+                None => error!("❌ User {} has a malformed wallet key", user_id),
+            }
+            user.private_key = private_key;
+        }
+
+        let added = diff_entries(user_id, "watchlist", &user.watchlist, &watchlist, &mut self.mint_index.write().unwrap());
+        for mint in added {
+            self.lifecycle.watch(user_id, &mint);
+        }
+        user.watchlist = watchlist;
+        diff_entries(user_id, "creator watchlist", &user.creator_watchlist, &creator_watchlist, &mut self.creator_index.write().unwrap());
+        user.creator_watchlist = creator_watchlist;
+
+        let mints: Vec<String> = user.watchlist.keys().cloned().collect();
+        drop(users);
+
+        for mint in mints {
+            self.index_mint(&mint);
+        }
     }
 
-    pub fn add_to_watchlist(&self, user_id: &String, mint: String, buy_amount: f64) -> Result<(), String> {
-        let mut users = self.users.lock().unwrap();
-        if let Some(user) = users.get_mut(user_id) {
-            user.watchlist.insert(mint.clone(), buy_amount);
-            info!("User {} added {} to watchlist ({} SOL)", user_id, mint, buy_amount);
-            
-            // ---------------------------------------------------------
-            // 1. Fetch Creator Address (Async Background Task)
-            // ---------------------------------------------------------
-            let rpc_url = self.rpc_url.clone();
-            let mint_clone = mint.clone();
-            let users_clone = self.users.clone();
-            let user_id_clone = user_id.clone();
-            
-            tokio::spawn(async move {
-                use solana_client::rpc_client::RpcClient;
-                use solana_sdk::pubkey::Pubkey;
-                use std::str::FromStr;
-
-                // Create a temporary RPC client for this fetch
-                let client = RpcClient::new(rpc_url);
-                if let Ok(mint_pk) = Pubkey::from_str(&mint_clone) {
-                    info!("Fetching creator for mint: {}", mint_clone);
-                    match crate::metadata::fetch_creator(&client, &mint_pk) {
-                        Ok(creator) => {
-                            info!("✅ Creator Fetched for {}: {}", mint_clone, creator);
-                            let mut users_guard = users_clone.lock().unwrap();
-                            if let Some(u) = users_guard.get_mut(&user_id_clone) {
-                                u.creators.insert(mint_clone.clone(), creator.to_string());
-                            }
-                        },
-                        Err(e) => {
-                            error!("❌ Failed to fetch creator for {}: {}", mint_clone, e);
-                            // Optional: Retry logic or default to secure mode
-                        }
-                    }
-                }
-            });
+    pub fn balances(&self) -> BalanceTracker {
+        self.balances.clone()
+    }
 
-            // ---------------------------------------------------------
-            // 2. Strategy B fallback: Derive Config PDAs for V1 and V2
-            // ---------------------------------------------------------
-            // Seeds: [b"fee_share_config", mint.key()]
-            use solana_sdk::pubkey::Pubkey;
-            use std::str::FromStr;
-            
-            if let Ok(mint_pk) = Pubkey::from_str(&mint) {
-                let mut vault_map = self.vault_to_mint.lock().unwrap();
-                
-                // Derive for V2
-                // Seeds: [b"fee_share_config", base_mint, quote_mint]
-                if let Ok(p2) = Pubkey::from_str("FEE2tBhCKAt7shrod19QttSVREUYPiyMzoku1mL1gqVK") {
-                    if let Ok(wsol) = Pubkey::from_str("So11111111111111111111111111111111111111112") {
-                        let (pda, _) = Pubkey::find_program_address(
-                            &[b"fee_share_config", mint_pk.as_ref(), wsol.as_ref()], 
-                            &p2
-                        );
-                        vault_map.insert(pda.to_string(), mint.clone());
-                        info!("Strategy B (V2) Registered: {} -> {}", pda, mint);
-                    }
+    pub fn risk(&self) -> RiskChecker {
+        self.risk.clone()
+    }
+
+    pub fn broadcaster(&self) -> Broadcaster {
+        self.broadcaster.clone()
+    }
+
+    pub fn tracker(&self) -> ConfirmationTracker {
+        self.tracker.clone()
+    }
+
+    pub fn blockhash(&self) -> BlockhashCache {
+        self.blockhash.clone()
+    }
+
+    /// Drop users that are no longer running
+    pub fn retain_users(&self, active: &HashSet<String>) {
+        let mut users = self.users.write().unwrap();
+        let mut index = self.mint_index.write().unwrap();
+        let mut creator_index = self.creator_index.write().unwrap();
+        users.retain(|uid, user| {
+            let keep = active.contains(uid);
+            if !keep {
+                info!("Removed user: {}", uid);
+                if let Some(wallet) = &user.wallet {
+                    self.balances.untrack(wallet);
+                }
+                for mint in user.watchlist.keys() {
+                    unindex_watcher(&mut index, uid, mint);
                 }
-                
-                // Derive for V1 (Official Legacy)
-                // V1 usually only uses the mint seed
-                if let Ok(p1) = Pubkey::from_str("FEEhPbKVKnco9EXnaY3i4R5rQVUx91wgVfu8qokixywi") {
-                    let (pda, _) = Pubkey::find_program_address(&[b"fee_share_config", mint_pk.as_ref()], &p1);
-                    vault_map.insert(pda.to_string(), mint.clone());
-                    info!("Strategy B (V1) Registered: {} -> {}", pda, mint);
+                for creator in user.creator_watchlist.keys() {
+                    unindex_watcher(&mut creator_index, uid, creator);
                 }
             }
+            keep
+        });
+    }
+
+    /// Derive fee share config PDAs once per mint and look up its creator.
+    /// Both are cached across users and syncs; failed creator lookups are
+    /// retried after `CREATOR_RETRY`.
+    fn index_mint(&self, mint: &str) {
+        use solana_sdk::pubkey::Pubkey;
+        use std::str::FromStr;
+
+        let mint_pk = match Pubkey::from_str(mint) {
+            Ok(pk) => pk,
+            Err(_) => return,
+        };
+
+        // Pre-trade risk report, kept fresh by the risk task
+        self.risk.watch(mint);
+
+        // ---------------------------------------------------------
+        // 1. Fetch Creator Address (Background Task)
+        // ---------------------------------------------------------
+        let should_fetch = {
+            let mut creators = self.creators.lock().unwrap();
+            let due = match creators.get(mint) {
+                None => true,
+                Some(CreatorLookup::Failed(at)) => at.elapsed() >= CREATOR_RETRY,
+                Some(_) => false,
+            };
+            if due {
+                creators.insert(mint.to_string(), CreatorLookup::Pending);
+            }
+            due
+        };
+
+        if should_fetch {
+            let client = self.metadata_client.clone();
+            let creators = self.creators.clone();
+            let mint = mint.to_string();
+
+            tokio::task::spawn_blocking(move || {
+                info!("Fetching creator for mint: {}", mint);
+                let lookup = match crate::metadata::fetch_creator(&client, &mint_pk) {
+                    Ok(creator) => {
+                        info!("✅ Creator Fetched for {}: {}", mint, creator);
+                        CreatorLookup::Known(creator.to_string())
+                    },
+                    Err(e) => {
+                        error!("❌ Failed to fetch creator for {}: {}", mint, e);
+                        CreatorLookup::Failed(Instant::now())
+                    }
+                };
+                creators.lock().unwrap().insert(mint, lookup);
+            });
+        }
+
+        // ---------------------------------------------------------
+        // 2. Strategy B fallback: Derive Config PDAs for V1 and V2
+        // ---------------------------------------------------------
+        // Seeds: [b"fee_share_config", mint.key()]
+        if !self.indexed_mints.lock().unwrap().insert(mint.to_string()) {
+            return;
+        }
+
+        let mut vault_map = self.vault_to_mint.lock().unwrap();
+
+        // Derive for V2
+        // Seeds: [b"fee_share_config", base_mint, quote_mint]
+        if let Ok(p2) = Pubkey::from_str("FEE2tBhCKAt7shrod19QttSVREUYPiyMzoku1mL1gqVK") {
+            if let Ok(wsol) = Pubkey::from_str("So11111111111111111111111111111111111111112") {
+                let (pda, _) = Pubkey::find_program_address(
+                    &[b"fee_share_config", mint_pk.as_ref(), wsol.as_ref()], 
+                    &p2
+                );
+                vault_map.insert(pda.to_string(), mint.to_string());
+                info!("Strategy B (V2) Registered: {} -> {}", pda, mint);
+            }
+        }
+
+        // Derive for V1 (Official Legacy)
+        // V1 usually only uses the mint seed
+        if let Ok(p1) = Pubkey::from_str("FEEhPbKVKnco9EXnaY3i4R5rQVUx91wgVfu8qokixywi") {
+            let (pda, _) = Pubkey::find_program_address(&[b"fee_share_config", mint_pk.as_ref()], &p1);
+            vault_map.insert(pda.to_string(), mint.to_string());
+            info!("Strategy B (V1) Registered: {} -> {}", pda, mint);
+        }
+    }
+
+    pub fn add_to_watchlist(&self, user_id: &String, mint: String, entry: WatchEntry) -> Result<(), String> {
+        let mut users = self.users.write().unwrap();
+        if let Some(user) = users.get_mut(user_id) {
+            if user.watchlist.get(&mint) != Some(&entry) {
+                info!("User {} added {} to watchlist ({} SOL, trigger: {:?})", user_id, mint, entry.buy_amount, entry.trigger_policy);
+                user.watchlist.insert(mint.clone(), entry);
+                self.mint_index.write().unwrap()
+                    .entry(mint.clone()).or_default()
+                    .insert(user_id.clone());
+                self.lifecycle.watch(user_id, &mint);
+            }
+            drop(users);
+            self.index_mint(&mint);
+            Ok(())
+        } else {
+            Err("User not found".to_string())
+        }
+    }
+
+    pub fn add_creator_watch(&self, user_id: &String, creator: String, entry: WatchEntry) -> Result<(), String> {
+        let mut users = self.users.write().unwrap();
+        if let Some(user) = users.get_mut(user_id) {
+            if user.creator_watchlist.get(&creator) != Some(&entry) {
+                info!("User {} added {} to creator watchlist ({} SOL)", user_id, creator, entry.buy_amount);
+                user.creator_watchlist.insert(creator.clone(), entry);
+                self.creator_index.write().unwrap()
+                    .entry(creator).or_default()
+                    .insert(user_id.clone());
+            }
+            Ok(())
+        } else {
+            Err("User not found".to_string())
+        }
+    }
+
+    pub fn remove_creator_watch(&self, user_id: &String, creator: &String) -> Result<(), String> {
+        let mut users = self.users.write().unwrap();
+        if let Some(user) = users.get_mut(user_id) {
+            if user.creator_watchlist.remove(creator).is_some() {
+                unindex_watcher(&mut self.creator_index.write().unwrap(), user_id, creator);
+                info!("User {} removed {} from creator watchlist", user_id, creator);
+            }
+            Ok(())
+        } else {
+            Err("User not found".to_string())
+        }
+    }
+
+    pub fn update_settings(&self, user_id: &String, settings: UserSettings) -> Result<(), String> {
+        let mut users = self.users.write().unwrap();
+        if let Some(user) = users.get_mut(user_id) {
+            if user.settings != settings {
+                info!("User {} settings updated", user_id);
+                user.settings = settings;
+            }
+            Ok(())
+        } else {
+            Err("User not found".to_string())
+        }
+    }
+
+    pub fn set_launch_rules(&self, user_id: &String, rules: Vec<LaunchRule>) -> Result<(), String> {
+        let mut users = self.users.write().unwrap();
+        if let Some(user) = users.get_mut(user_id) {
+            if user.launch_rules.len() != rules.len() {
+                info!("User {} has {} launch rule(s)", user_id, rules.len());
+            }
+            user.launch_rules = rules;
             Ok(())
         } else {
             Err("User not found".to_string())
         }
     }
 
+    /// Add a new launch to the watchlist of every user with a matching
+    /// launch rule (first matching rule wins). The Supabase row records
+    /// the rule as its source.
+    pub async fn handle_launch(&self, launch: &DetectedLaunch) {
+        let matched: Vec<(String, LaunchRuleRow)> = {
+            let users = self.users.read().unwrap();
+            users.iter()
+                .filter(|(_, user)| !user.watchlist.contains_key(&launch.mint))
+                .filter_map(|(uid, user)| {
+                    let rule = user.launch_rules.iter().find(|rule| rule.matches(launch))?;
+                    Some((uid.clone(), rule.row.clone()))
+                })
+                .collect()
+        };
+
+        for (uid, rule) in matched {
+            info!("🆕 Launch rule {} matched {} for user {}", rule.id, launch.mint, uid);
+            match self.supabase.add_launch_watch(&uid, launch, &rule).await {
+                Ok(Some(item)) => {
+                    self.add_to_watchlist(&uid, item.mint_address.clone(), WatchEntry::from(&item)).ok();
+                },
+                Ok(None) => {
+                    // Already on the watchlist (possibly inactive), leave it alone
+                },
+                Err(e) => {
+                    error!("❌ Failed to auto-watch {} for user {}: {}", launch.mint, uid, e);
+                    // Watch in memory anyway so the first claim isn't missed
+                    self.add_to_watchlist(&uid, launch.mint.clone(), WatchEntry::from(&rule)).ok();
+                    continue;
+                }
+            }
+            let message = format!("Auto-watching new launch {} ({}) via launch rule", launch.symbol, launch.mint);
+            self.supabase.log_activity(&uid, "INFO", &message).await.ok();
+        }
+    }
+
+    pub fn has_user(&self, user_id: &String) -> bool {
+        self.users.read().unwrap().contains_key(user_id)
+    }
+
+    pub fn remove_user(&self, user_id: &String) {
+        let mut users = self.users.write().unwrap();
+        if let Some(user) = users.remove(user_id) {
+            if let Some(wallet) = &user.wallet {
+                self.balances.untrack(wallet);
+            }
+            let mut index = self.mint_index.write().unwrap();
+            for mint in user.watchlist.keys() {
+                unindex_watcher(&mut index, user_id, mint);
+            }
+            let mut creator_index = self.creator_index.write().unwrap();
+            for creator in user.creator_watchlist.keys() {
+                unindex_watcher(&mut creator_index, user_id, creator);
+            }
+            info!("Removed user: {}", user_id);
+        }
+    }
+
     pub fn remove_from_watchlist(&self, user_id: &String, mint: &String) -> Result<(), String> {
-        let mut users = self.users.lock().unwrap();
+        let mut users = self.users.write().unwrap();
         if let Some(user) = users.get_mut(user_id) {
-            user.watchlist.remove(mint);
-            user.creators.remove(mint); // Cleanup
-            info!("User {} removed {} from watchlist", user_id, mint);
+            if user.watchlist.remove(mint).is_some() {
+                unindex_watcher(&mut self.mint_index.write().unwrap(), user_id, mint);
+                info!("User {} removed {} from watchlist", user_id, mint);
+            }
             Ok(())
         } else {
             Err("User not found".to_string())
         }
     }
 
+    /// Whether a user watches the mint, directly or through its creator
+    pub fn is_watched_mint(&self, mint: &str) -> bool {
+        if self.mint_index.read().unwrap().contains_key(mint) {
+            return true;
+        }
+        // Never hold creator_mints while reading creator_index (check_and_execute locks the other way)
+        let creator = match self.creator_mints.lock().unwrap().get(mint) {
+            Some(creator) => creator.clone(),
+            None => return false,
+        };
+        if self.creator_index.read().unwrap().contains_key(&creator) {
+            return true;
+        }
+        let mut creator_mints = self.creator_mints.lock().unwrap();
+        // Keep it if a newly watched creator claimed it in the meantime
+        if creator_mints.get(mint) == Some(&creator) {
+            creator_mints.remove(mint);
+        }
+        false
+    }
+
     pub fn get_watchlist(&self, user_id: &String) -> Option<Vec<String>> {
-        let users = self.users.lock().unwrap();
+        let users = self.users.read().unwrap();
         users.get(user_id).map(|u| u.watchlist.keys().cloned().collect())
     }
 
+    /// Seed trigger counters and spend ledgers from buys persisted before
+    /// a restart, so first-claim items that were already bought never fire
+    /// again and spend limits carry over.
+    pub fn seed_history(&self, buys: &[SnipeRecord], sniped: &[SnipeRecord]) -> usize {
+        let now = chrono::Utc::now();
+        let mut history: HashMap<String, (Vec<Duration>, bool)> = HashMap::new();
+        for record in buys {
+            let age = record.at
+                .and_then(|at| (now - at).to_std().ok())
+                .unwrap_or_default();
+            history.entry(format!("{}:{}", record.wallet_address, record.mint_address))
+                .or_default().0.push(age);
+        }
+        for record in sniped {
+            history.entry(format!("{}:{}", record.wallet_address, record.mint_address))
+                .or_default().1 = true;
+        }
+
+        for record in buys.iter().chain(sniped) {
+            self.lifecycle.seed_bought(&record.wallet_address, &record.mint_address);
+        }
+
+        let mut triggers = self.triggers.lock().unwrap();
+        for (key, (ages, was_sniped)) in &history {
+            triggers.insert(key.clone(), TriggerState::from_history(ages, *was_sniped));
+        }
+
+        let mut budgets = self.budgets.lock().unwrap();
+        for record in buys {
+            if let Some(amount) = record.amount_sol {
+                budgets.entry(record.wallet_address.clone())
+                    .or_default()
+                    .record(&record.mint_address, amount, record.at.unwrap_or(now));
+            }
+        }
+        history.len()
+    }
+
+    /// Restore persisted snipe states; call before `seed_history`
+    pub fn seed_snipes(&self, rows: &[SnipeRow]) {
+        self.lifecycle.seed(rows);
+    }
+
     pub fn add_vault_mapping(&self, vault: String, mint: String) {
         let mut map = self.vault_to_mint.lock().unwrap();
         map.insert(vault, mint);
     }
 
-    pub async fn check_and_execute(&self, involved_accounts: &HashSet<String>) {
+    pub async fn check_and_execute(&self, detected: &DetectedClaim) {
+        let claim = &detected.claim;
+        let source = detected.source;
         if let Some(jupiter) = &self.jupiter {
-            // Strategy Support: Resolve vaults to mints
-            let mut resolved_mints = involved_accounts.clone();
-            {
+            // Strategy A: the decoded base mint
+            let mut resolved_mints = HashSet::new();
+            if let Some(base_mint) = claim.base_mint() {
+                resolved_mints.insert(base_mint.to_string());
+            }
+            // Strategy B: resolve the fee share config to its mint
+            if let Some(config) = claim.fee_share_config() {
                 let vault_map = self.vault_to_mint.lock().unwrap();
-                for account in involved_accounts {
-                    if let Some(mint) = vault_map.get(account) {
-                        resolved_mints.insert(mint.clone());
+                if let Some(mint) = vault_map.get(config) {
+                    resolved_mints.insert(mint.clone());
+                }
+            }
+
+            // Look up only the users watching the resolved mints
+            let started = Instant::now();
+            // (uid, mint, creator if matched through the creator watchlist)
+            let mut watchers: Vec<(String, String, Option<String>)> = {
+                let index = self.mint_index.read().unwrap();
+                resolved_mints.iter()
+                    .filter_map(|mint| index.get(mint).map(|uids| (mint, uids)))
+                    .flat_map(|(mint, uids)| uids.iter().map(move |uid| (uid.clone(), mint.clone(), None)))
+                    .collect()
+            };
+            // Watched creators withdrawing their share (claim_user, V1 claim_a/claim_b) buy the claimed token
+            if let Some(base_mint) = claim.base_mint().filter(|_| claim.is_claimer_withdrawal()) {
+                let mut matched = Vec::new();
+                {
+                    let index = self.creator_index.read().unwrap();
+                    for signer in claim.signers() {
+                        for uid in index.get(signer).into_iter().flatten() {
+                            // A mint-level entry takes precedence
+                            if !watchers.iter().any(|(u, m, _)| u == uid && m == base_mint) {
+                                watchers.push((uid.clone(), base_mint.to_string(), Some(signer.to_string())));
+                            }
+                        }
+                        if index.contains_key(signer) {
+                            matched.push(signer.to_string());
+                        }
+                    }
+                }
+                // creator_index is released first (is_watched_mint locks the other way)
+                let creator_matched = !matched.is_empty();
+                for signer in matched {
+                    self.creator_mints.lock().unwrap().insert(base_mint.to_string(), signer);
+                }
+                if creator_matched {
+                    // The mint was never indexed, so there is no report yet: compute it now
+                    // when a matched user's buy depends on it, rather than skip that buy
+                    let needs_report = {
+                        let users = self.users.read().unwrap();
+                        watchers.iter()
+                            .filter(|(_, _, creator)| creator.is_some())
+                            .filter_map(|(uid, _, _)| users.get(uid))
+                            .any(|u| RiskPolicy::from(&u.settings).is_enabled())
+                    };
+                    if needs_report {
+                        self.risk.ensure_report(base_mint).await;
+                    } else {
+                        self.risk.watch(base_mint);
                     }
                 }
             }
+            if watchers.is_empty() {
+                self.match_stats.record(0, started.elapsed());
+                return;
+            }
 
-            let users_guard = self.users.lock().unwrap();
-            let mut sniped_guard = self.sniped.lock().unwrap();
-            
-            // Debug: Log registered users count  
-            info!("🔎 check_and_execute: {} registered users, {} accounts in tx", 
-                users_guard.len(), 
-                resolved_mints.len()
-            );
+            let users_guard = self.users.read().unwrap();
+            let creators_guard = self.creators.lock().unwrap();
+            let mut triggers_guard = self.triggers.lock().unwrap();
             
+            let mut budgets_guard = self.budgets.lock().unwrap();
+
             // Find all users who need to buy
-            // Tuple: (uid, mint, executor, private_key, buy_amount, slippage, priority_fee, telegram_user_id)
-            let mut actions: Vec<(String, String, crate::executor::TransactionExecutor, String, f64, u64, u64, Option<String>)> = Vec::new();
-
-            for (uid, user) in users_guard.iter() {
-                for (mint, buy_amount) in &user.watchlist {
-                    info!("🔎 Checking user {} watchlist mint {} against {} resolved accounts", 
-                        &uid[..8], 
-                        &mint[..12],
-                        resolved_mints.len()
-                    );
-                    if resolved_mints.contains(mint) {
-                        let idempotency_key = format!("{}:{}", uid, mint);
-                        if sniped_guard.contains(&idempotency_key) {
-                            continue; // Already sniped
+            let mut actions: Vec<PendingBuy> = Vec::new();
+            // (uid, log_type, message) activity entries for skipped or backfilled triggers
+            let mut notes: Vec<(String, &str, String)> = Vec::new();
+
+            for (uid, mint, creator) in &watchers {
+                let found = users_guard.get(uid).and_then(|u| {
+                    match creator {
+                        Some(creator) => u.creator_watchlist.get(creator),
+                        None => u.watchlist.get(mint),
+                    }.map(|e| (u, e))
+                });
+                let (user, entry) = match found {
+                    Some(found) => found,
+                    None => continue,
+                };
+                if let Some(creator) = creator {
+                    info!("👤 Watched creator {} claimed {} for user {}", creator, mint, uid);
+                }
+
+                if let Err(reason) = check_trigger_policy(entry.trigger_policy, claim, creators_guard.get(mint).and_then(CreatorLookup::known)) {
+                    info!("⏭️ Skipping trigger for user {} mint {}: {}", uid, mint, reason);
+                    notes.push((uid.clone(), "SKIP", format!("Skipped {} claim on {}: {}", claim.kind(), mint, reason)));
+                    continue;
+                }
+
+                if let Err(reason) = check_claim_size(entry.min_claim_amount, detected.claimed_amount) {
+                    info!("⏭️ Skipping trigger for user {} mint {}: {}", uid, mint, reason);
+                    notes.push((uid.clone(), "SKIP", format!("Skipped {} claim on {}: {}", claim.kind(), mint, reason)));
+                    continue;
+                }
+
+                if let ClaimSource::Backfill { age_secs } = source {
+                    if let Some(max_age) = user.settings.max_backfill_age_secs {
+                        if age_secs > max_age {
+                            info!("⏪ Skipping backfilled claim for user {} mint {}: {}s old (max {}s)", uid, mint, age_secs, max_age);
+                            notes.push((uid.clone(), "BACKFILL", format!("Skipped backfilled claim on {} ({}s old, max {}s)", mint, age_secs, max_age)));
+                            continue;
+                        }
+                    }
+                }
+
+                let risk_policy = RiskPolicy::from(&user.settings);
+                if risk_policy.is_enabled() {
+                    match self.risk.report(mint) {
+                        Some(report) => {
+                            if let Err(reason) = risk_policy.check(&report) {
+                                info!("🛡️ Skipping buy for user {} mint {}: {}", uid, mint, reason);
+                                notes.push((uid.clone(), "RISK", format!("Skipped buy on {}: {}", mint, reason)));
+                                continue;
+                            }
+                        }
+                        None => {
+                            info!("🛡️ Skipping buy for user {} mint {}: no risk report yet", uid, mint);
+                            notes.push((uid.clone(), "RISK", format!("Skipped buy on {}: risk report not ready yet", mint)));
+                            continue;
+                        }
+                    }
+                }
+
+                // The claim always counts; a later rejection only reverts the buy
+                let idempotency_key = format!("{}:{}", uid, mint);
+                let state = triggers_guard.entry(idempotency_key).or_default();
+                let before = state.clone();
+                if let Err(reason) = state.evaluate(&entry.trigger_rule, &detected.signature) {
+                    info!("⏭️ Skipping trigger for user {} mint {}: {}", uid, mint, reason);
+                    notes.push((uid.clone(), "SKIP", format!("Skipped {} claim on {}: {}", claim.kind(), mint, reason)));
+                    continue;
+                }
+
+                // Convert settings to lamports/bps
+                let slippage_bps = (user.settings.slippage * 100.0) as u64;
+                let priority_fee_lamports = (user.settings.priority_fee * 1_000_000_000.0) as u64;
+                let tip_lamports = (user.settings.bribe * 1_000_000_000.0) as u64;
+                let telegram_id = user.settings.telegram_user_id.clone();
+
+                // Size the buy for this claim
+                let balance = user.wallet.as_deref().and_then(|w| self.balances.available_sol(w));
+                let (sized, sizing) = match entry.sizing.amount(balance, detected.claimed_amount, &detected.quote_mint) {
+                    Ok(sized) => sized,
+                    Err(reason) => {
+                        info!("⏭️ Skipping buy for user {} mint {}: {}", uid, mint, reason);
+                        notes.push((uid.clone(), "SKIP", format!("Skipped buy on {}: {}", mint, reason)));
+                        state.revert_buy(&before);
+                        continue;
+                    }
+                };
+
+                // Hold the buy plus fees against the wallet, shrinking it if needed
+                let mut amount = sized;
+                let mut balance_hold = None;
+                if let Some(wallet) = &user.wallet {
+                    let wanted = (amount * 1_000_000_000.0) as u64;
+                    match self.balances.reserve(wallet, wanted, buy_overhead_lamports(priority_fee_lamports, tip_lamports)) {
+                        Ok(hold) => {
+                            if hold.amount_lamports < wanted {
+                                amount = hold.amount_lamports as f64 / 1_000_000_000.0;
+                                info!("🪫 Shrinking buy for user {} mint {} to {} SOL", uid, mint, amount);
+                                notes.push((uid.clone(), "BALANCE", format!("Shrunk buy on {} from {} to {} SOL to fit wallet balance", mint, sized, amount)));
+                            }
+                            balance_hold = Some((wallet.clone(), hold.id));
+                        }
+                        Err(reason) => {
+                            info!("🪫 Skipping buy for user {} mint {}: {}", uid, mint, reason);
+                            notes.push((uid.clone(), "BALANCE", format!("Skipped buy of {} SOL on {}: {}", sized, mint, reason)));
+                            state.revert_buy(&before);
+                            continue;
                         }
+                    }
+                }
 
-                        info!("🚀 USER {} TRIGGERED FOR MINT {}", uid, mint);
-                        sniped_guard.insert(idempotency_key);
-                        
-                        // Convert settings to lamports/bps
-                        let slippage_bps = (user.settings.slippage * 100.0) as u64;
-                        let priority_fee_lamports = (user.settings.priority_fee * 1_000_000_000.0) as u64;
-                        let telegram_id = user.settings.telegram_user_id.clone();
-
-                        actions.push((
-                            uid.clone(), 
-                            mint.clone(), 
-                            user.executor.clone(), 
-                            user.private_key.clone(),
-                            *buy_amount,
-                            slippage_bps,
-                            priority_fee_lamports,
-                            telegram_id
-                        ));
+                let ledger = budgets_guard.entry(uid.clone()).or_default();
+                let reservation = match ledger.reserve(&SpendLimits::from(&user.settings), mint, amount) {
+                    Ok(id) => id,
+                    Err(reason) => {
+                        info!("🛑 Spend limit for user {} mint {}: {}", uid, mint, reason);
+                        notes.push((uid.clone(), "LIMIT", format!("Skipped buy of {} SOL on {}: {}", amount, mint, reason)));
+                        if let Some((wallet, id)) = &balance_hold {
+                            self.balances.release(wallet, *id);
+                        }
+                        state.revert_buy(&before);
+                        continue;
                     }
+                };
+
+                if let ClaimSource::Backfill { age_secs } = source {
+                    notes.push((uid.clone(), "BACKFILL", format!("Backfilled claim on {} ({}s old) triggered buy", mint, age_secs)));
                 }
+
+                info!("🚀 USER {} TRIGGERED FOR MINT {} (claim {} @ slot {}, buy #{} of {:?} mode)", uid, mint, detected.signature, detected.slot, state.buys, entry.trigger_rule.mode);
+                self.lifecycle.claim_detected(uid, mint, &detected.signature);
+
+                actions.push(PendingBuy {
+                    uid: uid.clone(),
+                    mint: mint.clone(),
+                    executor: user.executor.clone(),
+                    private_key: user.private_key.clone(),
+                    amount,
+                    sizing,
+                    slippage_bps,
+                    priority_fee_lamports,
+                    tip_lamports,
+                    deadline: Duration::from_secs(user.settings.buy_deadline_secs),
+                    telegram_id,
+                    reservation,
+                    balance_hold,
+                });
             }
             drop(users_guard); 
-            drop(sniped_guard);
+            drop(creators_guard);
+            drop(triggers_guard);
+            drop(budgets_guard);
+            self.match_stats.record(watchers.len(), started.elapsed());
+
+            for (uid, log_type, message) in notes {
+                let sb = self.supabase.clone();
+                tokio::spawn(async move {
+                    sb.log_activity(&uid, log_type, &message).await.ok();
+                });
+            }
 
             // Execute trades concurrently
-            for (uid, mint, executor, private_key, amount, slippage, p_fee, telegram_id) in actions {
+            for PendingBuy { uid, mint, executor, private_key, amount, sizing, slippage_bps: slippage, priority_fee_lamports: p_fee, tip_lamports, deadline, telegram_id, reservation, balance_hold } in actions {
                 let jupiter = jupiter.clone();
+                let budgets = self.budgets.clone();
+                let balances = self.balances.clone();
+                let lifecycle = self.lifecycle.clone();
                 let sb = self.supabase.clone();
                 let tg = self.telegram.clone();
                 tokio::spawn(async move {
@@ -230,18 +947,69 @@ impl SniperManager {
                         let _ = tg.notify_claim_detected(tg_id, &mint).await;
                     }
                     
-                    match executor.buy_token(
-                        &private_key,
-                        &mint,
-                        amount,
-                        slippage,
-                        p_fee, 
-                        &jupiter
-                    ).await {
-                        Ok(sig) => {
+                    let order = BuyOrder {
+                        token_mint: mint.clone(),
+                        amount_sol: amount,
+                        slippage_bps: slippage,
+                        priority_fee_lamports: p_fee,
+                        deadline,
+                        tip_lamports,
+                    };
+                    // Every attempt gets its own trade log row, recorded before it is sent
+                    let trade_id: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+                    let report = {
+                        let (sb, uid, mint, sizing, lifecycle, trade_id) = (sb.clone(), uid.clone(), mint.clone(), sizing.clone(), lifecycle.clone(), trade_id.clone());
+                        move |event: AttemptEvent| {
+                            let (sb, uid, mint, sizing, lifecycle, trade_id) = (sb.clone(), uid.clone(), mint.clone(), sizing.clone(), lifecycle.clone(), trade_id.clone());
+                            async move {
+                                match event {
+                                    AttemptEvent::Started { attempt, amount_sol } => {
+                                        let id = sb.record_pending_buy(&uid, &mint, amount_sol, &sizing, attempt).await?;
+                                        *trade_id.lock().unwrap() = Some(id);
+                                    },
+                                    AttemptEvent::Sent { attempt, signature } => {
+                                        info!("📤 Sending buy attempt {} for user {}: {}", attempt, uid, signature);
+                                        lifecycle.tx_sent(&uid, &mint, &signature);
+                                    },
+                                    AttemptEvent::Tracking { signature, status } => {
+                                        tokio::spawn(follow_status(lifecycle, uid, mint, signature, status));
+                                    },
+                                    AttemptEvent::Failed { signature, error } => {
+                                        let id = trade_id.lock().unwrap().clone();
+                                        if let Some(id) = id {
+                                            let _ = sb.update_trade(&id, signature.as_deref(), "FAILED", Some(&error.to_string())).await;
+                                        }
+                                    },
+                                }
+                                Ok(())
+                            }
+                        }
+                    };
+                    // Returns once the first attempt is accepted; booked when the buy is confirmed
+                    let result = match executor.buy_token(private_key, order, jupiter, report).await {
+                        Ok(sent) => {
+                            info!("📨 Buy for user {} on {} sent: {}", uid, mint, sent.signature);
+                            sent.outcome.await.unwrap_or_else(|_| Err(anyhow!("Buy task stopped")))
+                        }
+                        Err(e) => Err(e),
+                    };
+                    let trade_id = trade_id.lock().unwrap().clone();
+
+                    match result {
+                        Ok(ConfirmedBuy { signature: sig, slot }) => {
                             info!("✅ Trade Success: {}", sig);
+                            // Real buys are confirmed by their status follower
+                            if executor.paper_trading {
+                                lifecycle.confirmed(&uid, &mint, &sig);
+                            }
+                            if let Some((wallet, id)) = &balance_hold {
+                                balances.settle(wallet, *id, slot);
+                            }
                             // Log success and mark as sniped
-                            let _ = sb.log_trade(&uid, &mint, "BUY", amount, Some(&sig), "SUCCESS", None).await;
+                            match trade_id {
+                                Some(ref id) => { let _ = sb.update_trade(id, Some(&sig), "SUCCESS", None).await; }
+                                None => { let _ = sb.log_trade(&uid, &mint, "BUY", amount, Some(&sig), "SUCCESS", None).await; }
+                            }
                             let _ = sb.mark_as_sniped(&uid, &mint).await;
                             
                             // Send Telegram notification
@@ -251,7 +1019,18 @@ impl SniperManager {
                         },
                         Err(e) => {
                             error!("❌ Trade Failed: {}", e);
-                            let _ = sb.log_trade(&uid, &mint, "BUY", amount, None, "FAILED", Some(&e.to_string())).await;
+                            lifecycle.failed(&uid, &mint, &e.to_string());
+                            // Failed buys don't count against spend limits
+                            if let Some(ledger) = budgets.lock().unwrap().get_mut(&uid) {
+                                ledger.release(reservation);
+                            }
+                            if let Some((wallet, id)) = &balance_hold {
+                                balances.release(wallet, *id);
+                            }
+                            // Recorded attempts already closed their own rows
+                            if trade_id.is_none() {
+                                let _ = sb.log_trade(&uid, &mint, "BUY", amount, None, "FAILED", Some(&e.to_string())).await;
+                            }
                             
                             // Send Telegram notification
                             if let Some(ref tg_id) = telegram_id {
@@ -263,8 +1042,25 @@ impl SniperManager {
             }
         }
     }
+    pub fn log_match_stats(&self) {
+        let stats = &self.match_stats;
+        let claims = stats.claims.load(Ordering::Relaxed);
+        if claims == 0 {
+            return;
+        }
+        let watched = self.mint_index.read().unwrap().len();
+        info!(
+            "⏱️ Claim matching: {} claims, {:.1} watchers/claim, avg {}µs, max {}µs ({} mints watched)",
+            claims,
+            stats.candidates.load(Ordering::Relaxed) as f64 / claims as f64,
+            stats.total_nanos.load(Ordering::Relaxed) / claims / 1_000,
+            stats.max_nanos.load(Ordering::Relaxed) / 1_000,
+            watched
+        );
+    }
+
     pub async fn broadcast_log(&self, log_type: &str, message: &str) {
-        let users_guard = self.users.lock().unwrap();
+        let users_guard = self.users.read().unwrap();
         let user_ids: Vec<String> = users_guard.keys().cloned().collect();
         drop(users_guard);
 
@@ -279,3 +1075,82 @@ impl SniperManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fee_share::{ClaimDamm, FeeShareClaim};
+    use crate::jupiter::JupiterClient;
+    use crate::launch::WSOL_MINT;
+    use crate::supabase::{BuyMode, SupabaseClient, TriggerMode};
+
+    const DEAD_URL: &str = "http://127.0.0.1:1";
+
+    fn manager() -> SniperManager {
+        let supabase = Arc::new(SupabaseClient::new(DEAD_URL.to_string(), String::new()));
+        let jupiter = Arc::new(JupiterClient::new(DEAD_URL.to_string()));
+        SniperManager::new(DEAD_URL.to_string(), supabase, Some(jupiter))
+    }
+
+    fn every_nth(n: u32) -> WatchEntry {
+        WatchEntry {
+            buy_amount: 0.1,
+            sizing: BuySizing::new(BuyMode::Fixed, 0.1, 0.0, &[]),
+            trigger_policy: TriggerPolicy::Any,
+            min_claim_amount: 0.0,
+            trigger_rule: TriggerRule { mode: TriggerMode::EveryNth, cooldown: Duration::ZERO, every_n: n, max_buys: 1 },
+        }
+    }
+
+    fn claim(mint: &str, signature: &str) -> DetectedClaim {
+        DetectedClaim {
+            claim: FeeShareClaim::Damm(ClaimDamm {
+                payer: "payer".to_string(),
+                fee_share_config: "config".to_string(),
+                fee_share_authority: "authority".to_string(),
+                fee_share_authority_quote_ata: "ata".to_string(),
+                partner: "partner".to_string(),
+                base_mint: mint.to_string(),
+                quote_mint: WSOL_MINT.to_string(),
+                pool: "pool".to_string(),
+            }),
+            signature: signature.to_string(),
+            slot: 1,
+            source: ClaimSource::Live,
+            claimed_amount: None,
+            quote_mint: WSOL_MINT.to_string(),
+        }
+    }
+
+    fn counters(manager: &SniperManager, key: &str) -> (u64, u32) {
+        let triggers = manager.triggers.lock().unwrap();
+        let state = &triggers[key];
+        (state.claims_seen, state.buys)
+    }
+
+    #[tokio::test]
+    async fn every_nth_fires_on_third_claim() {
+        let manager = manager();
+        let watchlist = HashMap::from([("mint".to_string(), every_nth(3))]);
+        manager.sync_user("user", String::new(), UserSettings::default(), watchlist, HashMap::new());
+
+        manager.check_and_execute(&claim("mint", "sig1")).await;
+        assert_eq!(counters(&manager, "user:mint"), (1, 0));
+        manager.check_and_execute(&claim("mint", "sig2")).await;
+        assert_eq!(counters(&manager, "user:mint"), (2, 0));
+        manager.check_and_execute(&claim("mint", "sig3")).await;
+        assert_eq!(counters(&manager, "user:mint"), (3, 1));
+    }
+
+    #[tokio::test]
+    async fn rejected_buy_still_counts_the_claim() {
+        let manager = manager();
+        let settings = UserSettings { max_buy_per_token: Some(0.0), ..UserSettings::default() };
+        let watchlist = HashMap::from([("mint".to_string(), every_nth(1))]);
+        manager.sync_user("user", String::new(), settings, watchlist, HashMap::new());
+
+        manager.check_and_execute(&claim("mint", "sig1")).await;
+        assert_eq!(counters(&manager, "user:mint"), (1, 0));
+        assert!(manager.triggers.lock().unwrap()["user:mint"].last_buy_at.is_none());
+    }
+}
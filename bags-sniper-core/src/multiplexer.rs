@@ -0,0 +1,484 @@
+//! Multi-endpoint Geyser racing for Bags Sniper
+//!
+//! Subscribes to several Yellowstone gRPC providers at once and merges
+//! their streams. The first copy of a transaction wins, later copies are
+//! dropped by signature, and per-provider win rate and lag are recorded.
+//!
+//! Each provider connection answers server pings, sends its own keepalive
+//! pings, and is torn down by a watchdog if it goes silent.
+
+use crate::geyser::GeyserConnection;
+use crate::manager::SniperManager;
+use yellowstone_grpc_proto::geyser::{subscribe_update::UpdateOneof, SubscribeUpdate};
+use futures::StreamExt;
+use tokio::sync::mpsc;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use log::{debug, error, info, warn};
+
+/// How long a signature is remembered for deduplication
+const DEDUPE_TTL: Duration = Duration::from_secs(120);
+/// Prune the dedupe map once it grows past this many entries
+const DEDUPE_PRUNE_THRESHOLD: usize = 10_000;
+const INITIAL_BACKOFF_SECS: u64 = 5;
+const MAX_BACKOFF_SECS: u64 = 60;
+const DEFAULT_STALL_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_PING_INTERVAL_SECS: u64 = 10;
+/// Provider index recorded for signatures claimed by backfill
+const BACKFILL_PROVIDER: usize = usize::MAX;
+
+/// Keepalive and watchdog timings shared by all provider connections
+#[derive(Debug, Clone)]
+pub struct StreamConfig {
+    /// Force a reconnect when no update arrives within this window
+    pub stall_timeout: Duration,
+    /// How often to send our own ping to the provider
+    pub ping_interval: Duration,
+}
+
+impl StreamConfig {
+    /// Read `GEYSER_STALL_TIMEOUT_SECS` and `GEYSER_PING_INTERVAL_SECS`
+    pub fn from_env() -> Self {
+        let secs = |key: &str, default: u64| {
+            std::env::var(key).ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .filter(|v| *v > 0)
+                .unwrap_or(default)
+        };
+        Self {
+            stall_timeout: Duration::from_secs(secs("GEYSER_STALL_TIMEOUT_SECS", DEFAULT_STALL_TIMEOUT_SECS)),
+            ping_interval: Duration::from_secs(secs("GEYSER_PING_INTERVAL_SECS", DEFAULT_PING_INTERVAL_SECS)),
+        }
+    }
+}
+
+/// A single Yellowstone gRPC provider
+#[derive(Debug, Clone)]
+pub struct GeyserEndpoint {
+    pub name: String,
+    pub url: String,
+    pub x_token: Option<String>,
+}
+
+impl GeyserEndpoint {
+    /// Build the endpoint list from `GRPC_URLS` (comma separated) or `GRPC_URL`.
+    /// Tokens come from `GRPC_X_TOKENS` (aligned with the URLs) or `GRPC_X_TOKEN`.
+    pub fn from_env() -> Vec<GeyserEndpoint> {
+        let urls: Vec<String> = std::env::var("GRPC_URLS")
+            .or_else(|_| std::env::var("GRPC_URL"))
+            .expect("GRPC_URLS or GRPC_URL must be set")
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        let shared_token = std::env::var("GRPC_X_TOKEN").ok();
+        let tokens: Vec<String> = std::env::var("GRPC_X_TOKENS")
+            .map(|v| v.split(',').map(|s| s.trim().to_string()).collect())
+            .unwrap_or_default();
+
+        urls.into_iter()
+            .enumerate()
+            .map(|(i, url)| {
+                let x_token = tokens.get(i)
+                    .filter(|t| !t.is_empty())
+                    .cloned()
+                    .or_else(|| shared_token.clone());
+                GeyserEndpoint {
+                    name: endpoint_name(&url),
+                    url,
+                    x_token,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Short provider label derived from the URL host
+fn endpoint_name(url: &str) -> String {
+    let without_scheme = url.split("://").last().unwrap_or(url);
+    without_scheme.split(['/', ':']).next().unwrap_or(without_scheme).to_string()
+}
+
+/// Slot range missed by every provider: slots in (from_slot, to_slot) were never delivered
+#[derive(Debug, Clone, Copy)]
+pub struct SlotGap {
+    /// Last slot processed before the outage
+    pub from_slot: u64,
+    /// First slot delivered after the outage
+    pub to_slot: u64,
+}
+
+/// An update tagged with the provider that delivered it
+pub struct RacedUpdate {
+    pub provider: usize,
+    pub update: SubscribeUpdate,
+    pub received_at: Instant,
+}
+
+/// Per-provider race statistics
+#[derive(Debug, Clone, Default)]
+pub struct ProviderStats {
+    pub name: String,
+    /// Transactions this provider delivered first
+    pub wins: u64,
+    /// Transactions this provider delivered at all (first or late)
+    pub seen: u64,
+    /// Sum of lag behind the winning copy, over late copies
+    pub total_lag: Duration,
+    pub connected: bool,
+    /// Highest slot this provider has delivered
+    pub last_slot: u64,
+    /// Connections torn down by the stall watchdog
+    pub stalls: u64,
+}
+
+impl ProviderStats {
+    pub fn win_rate(&self) -> f64 {
+        if self.seen == 0 {
+            return 0.0;
+        }
+        self.wins as f64 / self.seen as f64
+    }
+
+    /// Average lag behind the winner, counting wins as zero lag
+    pub fn avg_lag_ms(&self) -> f64 {
+        if self.seen == 0 {
+            return 0.0;
+        }
+        self.total_lag.as_secs_f64() * 1000.0 / self.seen as f64
+    }
+}
+
+struct FirstSeen {
+    at: Instant,
+    provider: usize,
+}
+
+#[derive(Clone)]
+pub struct StreamMultiplexer {
+    endpoints: Arc<Vec<GeyserEndpoint>>,
+    config: StreamConfig,
+    seen: Arc<Mutex<HashMap<Vec<u8>, FirstSeen>>>,
+    stats: Arc<Mutex<Vec<ProviderStats>>>,
+    // Highest slot delivered by any provider
+    last_slot: Arc<AtomicU64>,
+    gap_tx: Arc<Mutex<Option<mpsc::UnboundedSender<SlotGap>>>>,
+}
+
+impl StreamMultiplexer {
+    pub fn new(endpoints: Vec<GeyserEndpoint>, config: StreamConfig) -> Self {
+        let stats = endpoints.iter()
+            .map(|e| ProviderStats { name: e.name.clone(), ..Default::default() })
+            .collect();
+        Self {
+            endpoints: Arc::new(endpoints),
+            config,
+            seen: Arc::new(Mutex::new(HashMap::new())),
+            stats: Arc::new(Mutex::new(stats)),
+            last_slot: Arc::new(AtomicU64::new(0)),
+            gap_tx: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Highest slot seen across all providers (0 before the first update)
+    pub fn last_slot(&self) -> u64 {
+        self.last_slot.load(Ordering::Relaxed)
+    }
+
+    /// Spawn one reconnecting subscriber per endpoint, all feeding the returned channel
+    pub fn spawn(
+        &self,
+        program_ids: Vec<String>,
+        manager: Arc<SniperManager>,
+    ) -> mpsc::Receiver<RacedUpdate> {
+        let (tx, rx) = mpsc::channel(4096);
+        for (index, endpoint) in self.endpoints.iter().enumerate() {
+            let mux = self.clone();
+            let endpoint = endpoint.clone();
+            let program_ids = program_ids.clone();
+            let tx = tx.clone();
+            let manager = manager.clone();
+            tokio::spawn(async move {
+                mux.run_endpoint(index, endpoint, program_ids, tx, manager).await;
+            });
+        }
+        rx
+    }
+
+    /// gRPC connection loop for a single provider with auto-reconnect
+    async fn run_endpoint(
+        &self,
+        index: usize,
+        endpoint: GeyserEndpoint,
+        program_ids: Vec<String>,
+        tx: mpsc::Sender<RacedUpdate>,
+        manager: Arc<SniperManager>,
+    ) {
+        let mut backoff_secs = INITIAL_BACKOFF_SECS;
+
+        loop {
+            info!("🔌 [{}] Connecting to Yellowstone gRPC...", endpoint.name);
+
+            let geyser_result = GeyserConnection::connect(
+                endpoint.url.clone(),
+                endpoint.x_token.clone(),
+                None
+            ).await;
+
+            let mut geyser = match geyser_result {
+                Ok(g) => {
+                    backoff_secs = INITIAL_BACKOFF_SECS; // Reset backoff on successful connect
+                    g
+                }
+                Err(e) => {
+                    error!("❌ [{}] gRPC Connection Failed: {}. Retrying in {}s...", endpoint.name, e, backoff_secs);
+                    tokio::time::sleep(Duration::from_secs(backoff_secs)).await;
+                    backoff_secs = (backoff_secs * 2).min(MAX_BACKOFF_SECS);
+                    continue;
+                }
+            };
+
+            let (requests, mut stream) = match geyser.subscribe_programs(program_ids.clone()).await {
+                Ok(s) => s,
+                Err(e) => {
+                    error!("❌ [{}] gRPC Subscription Failed: {}. Retrying in {}s...", endpoint.name, e, backoff_secs);
+                    tokio::time::sleep(Duration::from_secs(backoff_secs)).await;
+                    backoff_secs = (backoff_secs * 2).min(MAX_BACKOFF_SECS);
+                    continue;
+                }
+            };
+
+            info!("👂 [{}] Listening for claim events on-chain...", endpoint.name);
+            self.set_connected(index, true);
+            manager.broadcast_log("INFO", &format!("Connected to gRPC stream ({}). Monitoring for claims...", endpoint.name)).await;
+
+            let mut ping_timer = tokio::time::interval(self.config.ping_interval);
+            ping_timer.tick().await; // First tick fires immediately
+            let mut ping_id: i32 = 0;
+            let mut resumed = false;
+            let mut last_update = Instant::now();
+
+            loop {
+                // Our own pings must not reset the watchdog, only provider updates do
+                let stall_deadline = tokio::time::Instant::from_std(last_update + self.config.stall_timeout);
+                let message = tokio::select! {
+                    _ = ping_timer.tick() => {
+                        ping_id = ping_id.wrapping_add(1);
+                        if requests.send(GeyserConnection::ping_request(ping_id)).await.is_err() {
+                            warn!("[{}] Keepalive ping failed, request stream closed", endpoint.name);
+                            break;
+                        }
+                        continue;
+                    }
+                    _ = tokio::time::sleep_until(stall_deadline) => None,
+                    next = stream.next() => {
+                        last_update = Instant::now();
+                        Some(next)
+                    }
+                };
+
+                let update = match message {
+                    Some(Some(Ok(update))) => update,
+                    Some(Some(Err(e))) => {
+                        error!("❌ [{}] gRPC Stream Error: {}. Reconnecting in {}s...", endpoint.name, e, backoff_secs);
+                        manager.broadcast_log("ERROR", &format!("Stream {} disconnected: {}. Reconnecting...", endpoint.name, e)).await;
+                        break; // Break inner loop to reconnect
+                    }
+                    Some(None) => {
+                        warn!("[{}] gRPC stream closed by server. Reconnecting in {}s...", endpoint.name, backoff_secs);
+                        break;
+                    }
+                    None => {
+                        error!(
+                            "⏱️ [{}] No update for {}s, stream stalled. Reconnecting in {}s...",
+                            endpoint.name,
+                            self.config.stall_timeout.as_secs(),
+                            backoff_secs
+                        );
+                        self.record_stall(index);
+                        manager.broadcast_log("ERROR", &format!("Stream {} stalled. Reconnecting...", endpoint.name)).await;
+                        break;
+                    }
+                };
+
+                let slot = match &update.update_oneof {
+                    Some(UpdateOneof::Ping(_)) => {
+                        // Answer server pings so the provider keeps the stream open
+                        ping_id = ping_id.wrapping_add(1);
+                        if requests.send(GeyserConnection::ping_request(ping_id)).await.is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                    Some(UpdateOneof::Pong(_)) => continue,
+                    Some(UpdateOneof::Slot(s)) => s.slot,
+                    Some(UpdateOneof::Transaction(t)) => t.slot,
+                    _ => 0,
+                };
+
+                if slot > 0 {
+                    let (previous, global_previous) = self.record_slot(index, slot);
+                    if !resumed {
+                        resumed = true;
+                        // Every provider missed these slots, hand the range to backfill
+                        if global_previous > 0 && slot > global_previous + 1 {
+                            self.report_gap(SlotGap { from_slot: global_previous, to_slot: slot });
+                        }
+                        if previous > 0 && slot > previous + 1 {
+                            warn!(
+                                "🕳️ [{}] Resumed at slot {} after reconnect, gap of {} slots since slot {}",
+                                endpoint.name,
+                                slot,
+                                slot - previous - 1,
+                                previous
+                            );
+                        } else if previous > 0 {
+                            info!("[{}] Resumed at slot {} with no gap", endpoint.name, slot);
+                        }
+                    }
+                }
+
+                if matches!(update.update_oneof, Some(UpdateOneof::Transaction(_))) {
+                    let raced = RacedUpdate { provider: index, update, received_at: Instant::now() };
+                    if tx.send(raced).await.is_err() {
+                        return; // Consumer gone, shutting down
+                    }
+                }
+            }
+
+            // Stream ended or errored - wait before reconnecting
+            self.set_connected(index, false);
+            tokio::time::sleep(Duration::from_secs(backoff_secs)).await;
+            backoff_secs = (backoff_secs * 2).min(MAX_BACKOFF_SECS);
+        }
+    }
+
+    /// Record a slot for a provider, returning the provider's and the global previous highest slot
+    fn record_slot(&self, index: usize, slot: u64) -> (u64, u64) {
+        let global_previous = self.last_slot.fetch_max(slot, Ordering::Relaxed);
+        let mut stats = self.stats.lock().unwrap();
+        let previous = match stats.get_mut(index) {
+            Some(s) => {
+                let previous = s.last_slot;
+                s.last_slot = s.last_slot.max(slot);
+                previous
+            }
+            None => 0,
+        };
+        (previous, global_previous)
+    }
+
+    fn report_gap(&self, gap: SlotGap) {
+        warn!("🕳️ All providers missed slots {}..{}, requesting backfill", gap.from_slot + 1, gap.to_slot);
+        if let Some(tx) = self.gap_tx.lock().unwrap().as_ref() {
+            let _ = tx.send(gap);
+        }
+    }
+
+    /// Receive slot ranges that no provider delivered (one receiver at a time)
+    pub fn subscribe_gaps(&self) -> mpsc::UnboundedReceiver<SlotGap> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        *self.gap_tx.lock().unwrap() = Some(tx);
+        rx
+    }
+
+    /// Claim a signature for processing outside the live stream (e.g. backfill).
+    /// Returns false if the signature was already seen.
+    pub fn claim_signature(&self, signature: &[u8]) -> bool {
+        let mut seen = self.seen.lock().unwrap();
+        if seen.contains_key(signature) {
+            return false;
+        }
+        seen.insert(signature.to_vec(), FirstSeen { at: Instant::now(), provider: BACKFILL_PROVIDER });
+        true
+    }
+
+    /// Release a claimed signature that could not be processed, so a later
+    /// backfill or a live provider can still deliver it
+    pub fn unclaim_signature(&self, signature: &[u8]) {
+        let mut seen = self.seen.lock().unwrap();
+        if seen.get(signature).map(|s| s.provider) == Some(BACKFILL_PROVIDER) {
+            seen.remove(signature);
+        }
+    }
+
+    fn record_stall(&self, index: usize) {
+        let mut stats = self.stats.lock().unwrap();
+        if let Some(s) = stats.get_mut(index) {
+            s.stalls += 1;
+        }
+    }
+
+    fn set_connected(&self, index: usize, connected: bool) {
+        let mut stats = self.stats.lock().unwrap();
+        if let Some(s) = stats.get_mut(index) {
+            s.connected = connected;
+        }
+    }
+
+    /// Record a raced update and return it only if it is the first copy seen.
+    /// Non-transaction updates are dropped.
+    pub fn accept(&self, raced: RacedUpdate) -> Option<SubscribeUpdate> {
+        let signature = match &raced.update.update_oneof {
+            Some(UpdateOneof::Transaction(tx)) => tx.transaction.as_ref()?.signature.clone(),
+            _ => return None,
+        };
+
+        let mut seen = self.seen.lock().unwrap();
+        let mut stats = self.stats.lock().unwrap();
+
+        if let Some(first) = seen.get(&signature) {
+            let lag = raced.received_at.saturating_duration_since(first.at);
+            if let Some(s) = stats.get_mut(raced.provider) {
+                s.seen += 1;
+                s.total_lag += lag;
+            }
+            debug!(
+                "Duplicate {} from {} ({}ms behind {})",
+                bs58::encode(&signature).into_string(),
+                stats[raced.provider].name,
+                lag.as_millis(),
+                stats.get(first.provider).map(|s| s.name.as_str()).unwrap_or("backfill")
+            );
+            return None;
+        }
+
+        if let Some(s) = stats.get_mut(raced.provider) {
+            s.seen += 1;
+            s.wins += 1;
+        }
+
+        if seen.len() >= DEDUPE_PRUNE_THRESHOLD {
+            let now = Instant::now();
+            seen.retain(|_, f| now.duration_since(f.at) < DEDUPE_TTL);
+        }
+        seen.insert(signature, FirstSeen { at: raced.received_at, provider: raced.provider });
+
+        Some(raced.update)
+    }
+
+    pub fn stats(&self) -> Vec<ProviderStats> {
+        self.stats.lock().unwrap().clone()
+    }
+
+    /// Log a one-line summary per provider
+    pub fn log_stats(&self) {
+        info!("📡 Last processed slot: {}", self.last_slot());
+        for s in self.stats() {
+            info!(
+                "📡 Provider {} | connected: {} | wins: {}/{} ({:.1}%) | avg lag: {:.1}ms | slot: {} | stalls: {}",
+                s.name,
+                s.connected,
+                s.wins,
+                s.seen,
+                s.win_rate() * 100.0,
+                s.avg_lag_ms(),
+                s.last_slot,
+                s.stalls
+            );
+        }
+    }
+}
@@ -0,0 +1,230 @@
+//! Buy Retry Policy for Bags Sniper
+//!
+//! Classifies failed buy attempts into typed errors and maps each class to
+//! how the next attempt is built: resend the same signed transaction,
+//! re-sign it with a fresh blockhash (optionally bumping the priority
+//! fee), re-quote through Jupiter, or give up.
+
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::transaction::TransactionError;
+use std::fmt;
+use std::time::Duration;
+
+/// Jupiter aggregator `SlippageToleranceExceeded` (0x1771)
+const JUPITER_SLIPPAGE_EXCEEDED: u32 = 6001;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BuyErrorKind {
+    /// Blockhash expired or unknown before the transaction landed
+    BlockhashNotFound,
+    SlippageExceeded,
+    InsufficientFunds,
+    /// RPC unreachable or no answer within the deadline
+    RpcTimeout,
+    /// Quote or swap request failed
+    Jupiter,
+    /// Bundle rejected by the block engine or not landed in time
+    Jito,
+    Other,
+}
+
+impl BuyErrorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BuyErrorKind::BlockhashNotFound => "blockhash_not_found",
+            BuyErrorKind::SlippageExceeded => "slippage_exceeded",
+            BuyErrorKind::InsufficientFunds => "insufficient_funds",
+            BuyErrorKind::RpcTimeout => "rpc_timeout",
+            BuyErrorKind::Jupiter => "jupiter",
+            BuyErrorKind::Jito => "jito",
+            BuyErrorKind::Other => "other",
+        }
+    }
+
+    pub fn policy(&self) -> RetryPolicy {
+        match self {
+            BuyErrorKind::BlockhashNotFound => RetryPolicy {
+                action: RetryAction::Resign,
+                max_retries: 3,
+                backoff: Duration::ZERO,
+                priority_fee_bump_pct: 25,
+            },
+            BuyErrorKind::SlippageExceeded => RetryPolicy {
+                action: RetryAction::Requote,
+                max_retries: 2,
+                backoff: Duration::ZERO,
+                priority_fee_bump_pct: 0,
+            },
+            BuyErrorKind::RpcTimeout => RetryPolicy {
+                action: RetryAction::Resend,
+                max_retries: 3,
+                backoff: Duration::from_millis(500),
+                priority_fee_bump_pct: 0,
+            },
+            BuyErrorKind::Jupiter => RetryPolicy {
+                action: RetryAction::Requote,
+                max_retries: 3,
+                backoff: Duration::from_millis(500),
+                priority_fee_bump_pct: 0,
+            },
+            BuyErrorKind::Jito => RetryPolicy {
+                action: RetryAction::Resign,
+                max_retries: 2,
+                backoff: Duration::ZERO,
+                priority_fee_bump_pct: 0,
+            },
+            BuyErrorKind::InsufficientFunds | BuyErrorKind::Other => RetryPolicy {
+                action: RetryAction::Abort,
+                max_retries: 0,
+                backoff: Duration::ZERO,
+                priority_fee_bump_pct: 0,
+            },
+        }
+    }
+}
+
+impl fmt::Display for BuyErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// How the next attempt is built
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryAction {
+    /// Send the same signed transaction again (same signature, can't double buy)
+    Resend,
+    /// Keep the swap, sign it again with a fresh blockhash
+    Resign,
+    /// Fetch a new quote and swap transaction
+    Requote,
+    Abort,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub action: RetryAction,
+    /// Retries allowed for this error class within one buy
+    pub max_retries: u32,
+    pub backoff: Duration,
+    /// Raise the compute unit price by this percent on each retry
+    pub priority_fee_bump_pct: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct BuyError {
+    pub kind: BuyErrorKind,
+    pub message: String,
+}
+
+impl BuyError {
+    pub fn new(kind: BuyErrorKind, message: impl Into<String>) -> Self {
+        Self { kind, message: message.into() }
+    }
+
+    pub fn jupiter(e: anyhow::Error) -> Self {
+        Self::new(BuyErrorKind::Jupiter, e.to_string())
+    }
+
+    /// Classify an RPC error from sending or confirming a transaction
+    pub fn from_client(e: &ClientError) -> Self {
+        let message = e.to_string();
+        let kind = match e.get_transaction_error() {
+            Some(tx_error) => classify_transaction_error(&tx_error),
+            None => match e.kind() {
+                ClientErrorKind::Io(_) | ClientErrorKind::Reqwest(_) => BuyErrorKind::RpcTimeout,
+                // send_and_confirm gives up with this once the blockhash has expired
+                _ if message.contains("unable to confirm transaction") => BuyErrorKind::BlockhashNotFound,
+                _ if message.to_lowercase().contains("slippage") => BuyErrorKind::SlippageExceeded,
+                _ => BuyErrorKind::Other,
+            },
+        };
+        Self { kind, message }
+    }
+
+    /// Classify a transaction that landed with an error
+    pub fn from_transaction(error: &TransactionError) -> Self {
+        Self::new(classify_transaction_error(error), error.to_string())
+    }
+
+    /// Classify a raw JSON-RPC error from a send endpoint
+    pub fn from_rpc_message(message: &str) -> Self {
+        let lower = message.to_lowercase();
+        let kind = if lower.contains("blockhash not found") {
+            BuyErrorKind::BlockhashNotFound
+        } else if lower.contains("insufficient") {
+            BuyErrorKind::InsufficientFunds
+        } else if lower.contains("slippage") || lower.contains("0x1771") {
+            BuyErrorKind::SlippageExceeded
+        } else {
+            BuyErrorKind::Other
+        };
+        Self::new(kind, message)
+    }
+}
+
+impl fmt::Display for BuyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.kind, self.message)
+    }
+}
+
+impl std::error::Error for BuyError {}
+
+fn classify_transaction_error(error: &TransactionError) -> BuyErrorKind {
+    match error {
+        TransactionError::BlockhashNotFound => BuyErrorKind::BlockhashNotFound,
+        TransactionError::InsufficientFundsForFee
+        | TransactionError::InsufficientFundsForRent { .. }
+        | TransactionError::InstructionError(_, InstructionError::InsufficientFunds) => BuyErrorKind::InsufficientFunds,
+        TransactionError::InstructionError(_, InstructionError::Custom(JUPITER_SLIPPAGE_EXCEEDED)) => BuyErrorKind::SlippageExceeded,
+        _ => BuyErrorKind::Other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_landed_transaction_errors() {
+        assert_eq!(classify_transaction_error(&TransactionError::BlockhashNotFound), BuyErrorKind::BlockhashNotFound);
+        assert_eq!(classify_transaction_error(&TransactionError::InsufficientFundsForFee), BuyErrorKind::InsufficientFunds);
+        assert_eq!(
+            classify_transaction_error(&TransactionError::InsufficientFundsForRent { account_index: 2 }),
+            BuyErrorKind::InsufficientFunds
+        );
+        assert_eq!(
+            classify_transaction_error(&TransactionError::InstructionError(3, InstructionError::InsufficientFunds)),
+            BuyErrorKind::InsufficientFunds
+        );
+    }
+
+    #[test]
+    fn jupiter_slippage_is_retried_with_a_new_quote() {
+        let error = TransactionError::InstructionError(4, InstructionError::Custom(JUPITER_SLIPPAGE_EXCEEDED));
+        assert_eq!(classify_transaction_error(&error), BuyErrorKind::SlippageExceeded);
+        assert_eq!(BuyError::from_transaction(&error).kind.policy().action, RetryAction::Requote);
+    }
+
+    #[test]
+    fn other_program_errors_abort() {
+        for error in [
+            TransactionError::InstructionError(4, InstructionError::Custom(JUPITER_SLIPPAGE_EXCEEDED + 1)),
+            TransactionError::InstructionError(0, InstructionError::InvalidAccountData),
+            TransactionError::AccountInUse,
+        ] {
+            let kind = classify_transaction_error(&error);
+            assert_eq!(kind, BuyErrorKind::Other, "{}", error);
+            assert_eq!(kind.policy().action, RetryAction::Abort);
+        }
+    }
+
+    #[test]
+    fn classifies_rpc_messages() {
+        assert_eq!(BuyError::from_rpc_message("Transaction simulation failed: Blockhash not found").kind, BuyErrorKind::BlockhashNotFound);
+        assert_eq!(BuyError::from_rpc_message("custom program error: 0x1771").kind, BuyErrorKind::SlippageExceeded);
+        assert_eq!(BuyError::from_rpc_message("Attempt to debit an account but found no record of a prior credit. insufficient lamports").kind, BuyErrorKind::InsufficientFunds);
+    }
+}
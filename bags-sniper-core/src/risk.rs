@@ -0,0 +1,432 @@
+//! Pre-trade Risk Filters for Bags Sniper
+//!
+//! Builds a risk report per watched mint: live mint authority, freeze
+//! authority, Token-2022 transfer fee / transfer hook extensions and the
+//! share of supply held by the top wallets. Reports are computed when a
+//! mint is watched and refreshed on a timer, so the buy path only reads
+//! the cache. Each user picks which findings block a buy.
+
+use crate::supabase::UserSettings;
+use anyhow::{anyhow, Result};
+use log::{info, warn};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const TOKEN_PROGRAM_ID: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+const TOKEN_2022_PROGRAM_ID: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+
+/// Base SPL mint layout size; Token-2022 extensions start after the
+/// account type byte that follows the 165-byte token account padding
+const MINT_LEN: usize = 82;
+const ACCOUNT_TYPE_OFFSET: usize = 165;
+const EXTENSION_TRANSFER_FEE_CONFIG: u16 = 1;
+const EXTENSION_TRANSFER_HOOK: u16 = 14;
+
+/// Holders counted towards concentration
+const TOP_HOLDERS: usize = 10;
+
+/// What we know about a mint's risk
+#[derive(Debug, Clone)]
+pub struct RiskReport {
+    pub token_2022: bool,
+    pub mint_authority: Option<String>,
+    pub freeze_authority: Option<String>,
+    /// Highest of the current and scheduled transfer fee
+    pub transfer_fee_bps: u16,
+    pub transfer_hook_program: Option<String>,
+    /// Percent of supply held by the top wallets (program-owned pools and vaults excluded)
+    pub top_holders_pct: Option<f64>,
+}
+
+/// Which findings block a buy for one user
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RiskPolicy {
+    pub block_mint_authority: bool,
+    pub block_freeze_authority: bool,
+    pub block_transfer_fee: bool,
+    pub block_transfer_hook: bool,
+    pub max_top_holders_pct: Option<f64>,
+}
+
+impl From<&UserSettings> for RiskPolicy {
+    fn from(settings: &UserSettings) -> Self {
+        Self {
+            block_mint_authority: settings.risk_block_mint_authority,
+            block_freeze_authority: settings.risk_block_freeze_authority,
+            block_transfer_fee: settings.risk_block_transfer_fee,
+            block_transfer_hook: settings.risk_block_transfer_hook,
+            max_top_holders_pct: settings.risk_max_top_holders_pct,
+        }
+    }
+}
+
+impl RiskPolicy {
+    pub fn is_enabled(&self) -> bool {
+        self.block_mint_authority
+            || self.block_freeze_authority
+            || self.block_transfer_fee
+            || self.block_transfer_hook
+            || self.max_top_holders_pct.is_some()
+    }
+
+    /// Err with every blocking finding
+    pub fn check(&self, report: &RiskReport) -> Result<(), String> {
+        let mut findings = Vec::new();
+        if self.block_mint_authority {
+            if let Some(authority) = &report.mint_authority {
+                findings.push(format!("mint authority still set ({})", authority));
+            }
+        }
+        if self.block_freeze_authority {
+            if let Some(authority) = &report.freeze_authority {
+                findings.push(format!("freeze authority set ({})", authority));
+            }
+        }
+        if self.block_transfer_fee && report.transfer_fee_bps > 0 {
+            findings.push(format!("transfer fee of {} bps", report.transfer_fee_bps));
+        }
+        if self.block_transfer_hook {
+            if let Some(program) = &report.transfer_hook_program {
+                findings.push(format!("transfer hook ({})", program));
+            }
+        }
+        if let (Some(max), Some(pct)) = (self.max_top_holders_pct, report.top_holders_pct) {
+            if pct > max {
+                findings.push(format!("top {} holders own {:.1}% (max {:.1}%)", TOP_HOLDERS, pct, max));
+            }
+        }
+        if findings.is_empty() {
+            Ok(())
+        } else {
+            Err(findings.join(", "))
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RiskChecker {
+    reports: Arc<Mutex<HashMap<String, RiskReport>>>,
+    /// Mints with a report being computed
+    pending: Arc<Mutex<HashSet<String>>>,
+    rpc_client: Arc<RpcClient>,
+}
+
+impl RiskChecker {
+    pub fn new(rpc_url: String) -> Self {
+        Self {
+            reports: Arc::new(Mutex::new(HashMap::new())),
+            pending: Arc::new(Mutex::new(HashSet::new())),
+            rpc_client: Arc::new(RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed())),
+        }
+    }
+
+    /// Compute a report for a newly watched mint (no-op if known or in progress)
+    pub fn watch(&self, mint: &str) {
+        if self.reports.lock().unwrap().contains_key(mint) {
+            return;
+        }
+        self.refresh(mint);
+    }
+
+    /// Cached report, if one has been computed
+    pub fn report(&self, mint: &str) -> Option<RiskReport> {
+        self.reports.lock().unwrap().get(mint).cloned()
+    }
+
+    /// Recompute a mint's report in the background
+    fn refresh(&self, mint: &str) {
+        if !self.pending.lock().unwrap().insert(mint.to_string()) {
+            return;
+        }
+        let checker = self.clone();
+        let mint = mint.to_string();
+        tokio::spawn(async move {
+            match checker.compute(&mint).await {
+                Ok(report) => checker.store(&mint, report),
+                Err(e) => warn!("⚠️ Risk check failed for {}: {}", mint, e),
+            }
+            checker.pending.lock().unwrap().remove(&mint);
+        });
+    }
+
+    /// Cached report, or one computed right now if there is none yet.
+    /// For mints first seen in the claim being acted on.
+    pub async fn ensure_report(&self, mint: &str) -> Option<RiskReport> {
+        if let Some(report) = self.report(mint) {
+            return Some(report);
+        }
+        match self.compute(mint).await {
+            Ok(report) => {
+                self.store(mint, report.clone());
+                Some(report)
+            }
+            Err(e) => {
+                warn!("⚠️ Risk check failed for {}: {}", mint, e);
+                None
+            }
+        }
+    }
+
+    fn store(&self, mint: &str, report: RiskReport) {
+        info!(
+            "🛡️ Risk report for {} ({}): mint authority: {} | freeze authority: {} | transfer fee: {} bps | hook: {} | top {} holders: {}",
+            mint,
+            if report.token_2022 { "Token-2022" } else { "SPL Token" },
+            report.mint_authority.as_deref().unwrap_or("none"),
+            report.freeze_authority.as_deref().unwrap_or("none"),
+            report.transfer_fee_bps,
+            report.transfer_hook_program.as_deref().unwrap_or("none"),
+            TOP_HOLDERS,
+            report.top_holders_pct.map(|p| format!("{:.1}%", p)).unwrap_or_else(|| "unknown".to_string())
+        );
+        self.reports.lock().unwrap().insert(mint.to_string(), report);
+    }
+
+    async fn compute(&self, mint: &str) -> Result<RiskReport> {
+        let mint_pk = Pubkey::from_str(mint)?;
+        let account = self.rpc_client.get_account(&mint_pk).await?;
+        let owner = account.owner.to_string();
+        if owner != TOKEN_PROGRAM_ID && owner != TOKEN_2022_PROGRAM_ID {
+            return Err(anyhow!("not a token mint (owner {})", owner));
+        }
+        let mut report = parse_mint(&account.data, owner == TOKEN_2022_PROGRAM_ID)?;
+        let supply = read_u64(&account.data, 36).unwrap_or(0);
+        report.top_holders_pct = match self.top_holders_amount(&mint_pk).await {
+            Ok(held) if supply > 0 => Some(held as f64 * 100.0 / supply as f64),
+            Ok(_) => None,
+            Err(e) => {
+                warn!("⚠️ Could not read holders of {}: {}", mint, e);
+                None
+            }
+        };
+        Ok(report)
+    }
+
+    /// Tokens held by the largest wallet-owned accounts. Accounts owned by
+    /// PDAs (bonding curve and pool vaults) are not holders.
+    async fn top_holders_amount(&self, mint: &Pubkey) -> Result<u64> {
+        let largest = self.rpc_client.get_token_largest_accounts(mint).await?;
+        let addresses: Vec<Pubkey> = largest.iter()
+            .filter_map(|a| Pubkey::from_str(&a.address).ok())
+            .collect();
+        let accounts = self.rpc_client.get_multiple_accounts(&addresses).await?;
+
+        let held = largest.iter()
+            .zip(accounts)
+            .filter_map(|(balance, account)| {
+                let owner = Pubkey::try_from(account?.data.get(32..64)?).ok()?;
+                owner.is_on_curve().then(|| balance.amount.amount.parse::<u64>().ok())?
+            })
+            .take(TOP_HOLDERS)
+            .sum();
+        Ok(held)
+    }
+
+    /// Refresh every known report on a timer, forever, first dropping the
+    /// reports of mints `watched` no longer reports as watched
+    pub async fn run<W>(self, interval: Duration, watched: W)
+    where
+        W: Fn(&str) -> bool,
+    {
+        let mut timer = tokio::time::interval(interval);
+        timer.tick().await;
+        loop {
+            timer.tick().await;
+            let mints: Vec<String> = {
+                let mut reports = self.reports.lock().unwrap();
+                let before = reports.len();
+                reports.retain(|mint, _| watched(mint));
+                if reports.len() < before {
+                    info!("🛡️ Dropped {} risk report(s) for unwatched mints", before - reports.len());
+                }
+                reports.keys().cloned().collect()
+            };
+            for mint in mints {
+                self.refresh(&mint);
+            }
+        }
+    }
+}
+
+/// Authorities and Token-2022 extensions from raw mint data
+fn parse_mint(data: &[u8], token_2022: bool) -> Result<RiskReport> {
+    if data.len() < MINT_LEN {
+        return Err(anyhow!("mint account too short ({} bytes)", data.len()));
+    }
+    let mut report = RiskReport {
+        token_2022,
+        mint_authority: read_coption_pubkey(data, 0),
+        freeze_authority: read_coption_pubkey(data, 46),
+        transfer_fee_bps: 0,
+        transfer_hook_program: None,
+        top_holders_pct: None,
+    };
+
+    // Type-length-value extensions after the account type byte
+    let mut offset = ACCOUNT_TYPE_OFFSET + 1;
+    while token_2022 && offset + 4 <= data.len() {
+        let ty = u16::from_le_bytes([data[offset], data[offset + 1]]);
+        let len = u16::from_le_bytes([data[offset + 2], data[offset + 3]]) as usize;
+        let value = match data.get(offset + 4..offset + 4 + len) {
+            Some(v) => v,
+            None => break,
+        };
+        match ty {
+            0 => break, // Uninitialized: no more extensions
+            EXTENSION_TRANSFER_FEE_CONFIG => {
+                // Two authorities and the withheld amount, then older and newer fees
+                // (epoch u64, maximum_fee u64, basis_points u16)
+                let older = value.get(88..90).map(|b| u16::from_le_bytes([b[0], b[1]])).unwrap_or(0);
+                let newer = value.get(106..108).map(|b| u16::from_le_bytes([b[0], b[1]])).unwrap_or(0);
+                report.transfer_fee_bps = older.max(newer);
+            }
+            EXTENSION_TRANSFER_HOOK => {
+                // Authority, then the hook program (all zeroes when unset)
+                if let Some(program) = value.get(32..64).filter(|p| p.iter().any(|b| *b != 0)) {
+                    report.transfer_hook_program = Some(bs58::encode(program).into_string());
+                }
+            }
+            _ => {}
+        }
+        offset += 4 + len;
+    }
+    Ok(report)
+}
+
+/// COption<Pubkey>: u32 tag, then the key
+fn read_coption_pubkey(data: &[u8], offset: usize) -> Option<String> {
+    let tag = data.get(offset..offset + 4)?;
+    if tag != [1, 0, 0, 0] {
+        return None;
+    }
+    Some(bs58::encode(data.get(offset + 4..offset + 36)?).into_string())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(data.get(offset..offset + 8)?.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Base mint layout: COption mint authority, supply, decimals,
+    /// is_initialized, COption freeze authority
+    fn mint_data(mint_authority: Option<Pubkey>, freeze_authority: Option<Pubkey>) -> Vec<u8> {
+        let mut data = Vec::with_capacity(MINT_LEN);
+        let coption = |data: &mut Vec<u8>, key: Option<Pubkey>| match key {
+            Some(key) => {
+                data.extend_from_slice(&[1, 0, 0, 0]);
+                data.extend_from_slice(key.as_ref());
+            }
+            None => data.extend_from_slice(&[0; 36]),
+        };
+        coption(&mut data, mint_authority);
+        data.extend_from_slice(&1_000_000u64.to_le_bytes());
+        data.push(6);
+        data.push(1);
+        coption(&mut data, freeze_authority);
+        data
+    }
+
+    /// Token-2022 mint: padded to the account type byte, then TLV extensions
+    fn token_2022_data(extensions: &[(u16, Vec<u8>)]) -> Vec<u8> {
+        let mut data = mint_data(None, None);
+        data.resize(ACCOUNT_TYPE_OFFSET, 0);
+        data.push(1); // AccountType::Mint
+        for (ty, value) in extensions {
+            data.extend_from_slice(&ty.to_le_bytes());
+            data.extend_from_slice(&(value.len() as u16).to_le_bytes());
+            data.extend_from_slice(value);
+        }
+        data
+    }
+
+    fn transfer_fee_config(older_bps: u16, newer_bps: u16) -> Vec<u8> {
+        let mut value = vec![0u8; 72];
+        for bps in [older_bps, newer_bps] {
+            value.extend_from_slice(&0u64.to_le_bytes());
+            value.extend_from_slice(&u64::MAX.to_le_bytes());
+            value.extend_from_slice(&bps.to_le_bytes());
+        }
+        value
+    }
+
+    fn transfer_hook(program: Option<Pubkey>) -> Vec<u8> {
+        let mut value = vec![0u8; 32];
+        value.extend_from_slice(program.map(|p| p.to_bytes()).unwrap_or_default().as_ref());
+        value
+    }
+
+    #[test]
+    fn reads_authorities() {
+        let (mint_auth, freeze_auth) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let report = parse_mint(&mint_data(Some(mint_auth), Some(freeze_auth)), false).unwrap();
+        assert_eq!(report.mint_authority, Some(mint_auth.to_string()));
+        assert_eq!(report.freeze_authority, Some(freeze_auth.to_string()));
+
+        let report = parse_mint(&mint_data(None, None), false).unwrap();
+        assert_eq!(report.mint_authority, None);
+        assert_eq!(report.freeze_authority, None);
+    }
+
+    #[test]
+    fn rejects_short_data() {
+        assert!(parse_mint(&[0; MINT_LEN - 1], false).is_err());
+    }
+
+    #[test]
+    fn spl_mint_ignores_trailing_bytes() {
+        let mut data = token_2022_data(&[(EXTENSION_TRANSFER_FEE_CONFIG, transfer_fee_config(500, 500))]);
+        data[..MINT_LEN].copy_from_slice(&mint_data(None, None));
+        let report = parse_mint(&data, false).unwrap();
+        assert_eq!(report.transfer_fee_bps, 0);
+    }
+
+    #[test]
+    fn reads_the_higher_transfer_fee() {
+        let data = token_2022_data(&[(EXTENSION_TRANSFER_FEE_CONFIG, transfer_fee_config(100, 250))]);
+        assert_eq!(parse_mint(&data, true).unwrap().transfer_fee_bps, 250);
+        let data = token_2022_data(&[(EXTENSION_TRANSFER_FEE_CONFIG, transfer_fee_config(300, 0))]);
+        assert_eq!(parse_mint(&data, true).unwrap().transfer_fee_bps, 300);
+    }
+
+    #[test]
+    fn reads_the_transfer_hook_program() {
+        let program = Pubkey::new_unique();
+        let data = token_2022_data(&[(EXTENSION_TRANSFER_HOOK, transfer_hook(Some(program)))]);
+        assert_eq!(parse_mint(&data, true).unwrap().transfer_hook_program, Some(program.to_string()));
+
+        let data = token_2022_data(&[(EXTENSION_TRANSFER_HOOK, transfer_hook(None))]);
+        assert_eq!(parse_mint(&data, true).unwrap().transfer_hook_program, None);
+    }
+
+    #[test]
+    fn skips_unknown_extensions() {
+        let program = Pubkey::new_unique();
+        let data = token_2022_data(&[
+            (18, vec![7; 64]), // metadata pointer
+            (EXTENSION_TRANSFER_FEE_CONFIG, transfer_fee_config(0, 50)),
+            (EXTENSION_TRANSFER_HOOK, transfer_hook(Some(program))),
+        ]);
+        let report = parse_mint(&data, true).unwrap();
+        assert_eq!(report.transfer_fee_bps, 50);
+        assert_eq!(report.transfer_hook_program, Some(program.to_string()));
+    }
+
+    #[test]
+    fn stops_at_a_truncated_extension() {
+        let mut data = token_2022_data(&[(EXTENSION_TRANSFER_FEE_CONFIG, transfer_fee_config(0, 50))]);
+        data.extend_from_slice(&EXTENSION_TRANSFER_HOOK.to_le_bytes());
+        data.extend_from_slice(&64u16.to_le_bytes());
+        data.extend_from_slice(&[9; 10]);
+        let report = parse_mint(&data, true).unwrap();
+        assert_eq!(report.transfer_fee_bps, 50);
+        assert_eq!(report.transfer_hook_program, None);
+    }
+}
@@ -1,9 +1,43 @@
+use crate::fee_share::{FeeShareClaim, FeeShareDecoder};
+use crate::launch::detect_launch;
 use crate::manager::SniperManager;
 use yellowstone_grpc_proto::geyser::{SubscribeUpdate, SubscribeUpdateTransaction};
-use std::collections::HashSet;
+use yellowstone_grpc_proto::prelude::{CompiledInstruction, InnerInstruction, Message, TokenBalance, TransactionStatusMeta};
 use std::sync::Arc;
 use log::info;
 
+/// Where a claim transaction was observed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClaimSource {
+    /// Delivered by the live Geyser stream
+    Live,
+    /// Recovered over RPC after a stream outage
+    Backfill { age_secs: u64 },
+}
+
+/// A decoded claim plus what we learned about it from the transaction
+#[derive(Debug, Clone)]
+pub struct DetectedClaim {
+    pub claim: FeeShareClaim,
+    pub signature: String,
+    pub slot: u64,
+    pub source: ClaimSource,
+    /// Quote amount (SOL for WSOL pairs) the recipient actually received, if measurable
+    pub claimed_amount: Option<f64>,
+    /// Mint `claimed_amount` is denominated in
+    pub quote_mint: String,
+}
+
+/// Transaction-level data shared by every instruction check
+#[derive(Clone, Copy)]
+struct TxContext<'a> {
+    slot: u64,
+    sig: &'a str,
+    account_keys: &'a [Vec<u8>],
+    meta: Option<&'a TransactionStatusMeta>,
+    source: ClaimSource,
+}
+
 #[derive(Clone)]
 pub struct Sniper {
     manager: Arc<SniperManager>,
@@ -18,14 +52,14 @@ impl Sniper {
         if let Some(update_oneof) = update.update_oneof {
              match update_oneof {
                  yellowstone_grpc_proto::geyser::subscribe_update::UpdateOneof::Transaction(tx_update) => {
-                     self.process_transaction(tx_update).await;
+                     self.process_transaction(tx_update, ClaimSource::Live).await;
                  }
                  _ => {}
              }
         }
     }
 
-    async fn process_transaction(&self, tx: SubscribeUpdateTransaction) {
+    pub async fn process_transaction(&self, tx: SubscribeUpdateTransaction, source: ClaimSource) {
         let slot = tx.slot;
         let tx_info = match tx.transaction {
             Some(t) => t,
@@ -37,81 +71,269 @@ impl Sniper {
              Some(t) => t.message,
              None => return,
         };
-        
-        let account_keys = match message.as_ref() {
-            Some(m) => &m.account_keys,
+
+        let message = match message {
+            Some(m) => m,
             None => return,
         };
 
-        let instructions = match message.as_ref() {
-            Some(m) => &m.instructions,
-            None => return,
+        // v0 transactions index past the static keys into the loaded LUT addresses
+        let account_keys = full_account_keys(&message, tx_info.meta.as_ref());
+        let instructions = &message.instructions;
+
+        let ctx = TxContext {
+            slot,
+            sig: &sig,
+            account_keys: &account_keys,
+            meta: tx_info.meta.as_ref(),
+            source,
         };
 
-        // Official Bags Fee Share V2 Discriminators from IDL
-        // claim_damm_v2: [232, 175, 106, 19, 168, 54, 186, 108] - Protocol fee distribution
-        // claim_dbc: [229, 142, 38, 65, 198, 50, 110, 58] - DBC fee distribution
-        // claim_user: [164, 64, 55, 199, 90, 78, 147, 188] - User claiming their fees (CREATOR CLAIM!)
-        let claim_damm_v2: [u8; 8] = [232, 175, 106, 19, 168, 54, 186, 108];
-        let claim_dbc: [u8; 8] = [229, 142, 38, 65, 198, 50, 110, 58];
-        let claim_user: [u8; 8] = [164, 64, 55, 199, 90, 78, 147, 188];
+        // New Bags launches feed the users' auto-watch rules; replayed ones are stale
+        if !matches!(source, ClaimSource::Backfill { .. }) {
+            if let Some(launch) = detect_launch(&sig, &account_keys, instructions) {
+                info!("🚀 [SLOT {}] Bags LAUNCH Detected! | Sig: {}... | {}", slot, &sig[..10], launch);
+                self.manager.handle_launch(&launch).await;
+            }
+        }
 
+        // Top-level instructions
         for inst in instructions {
-            // Get the program ID for this instruction
-            let program_id = match account_keys.get(inst.program_id_index as usize) {
-                Some(k) => bs58::encode(k).into_string(),
-                None => continue,
-            };
-
-            // Monitor Bags Fee Share V1 & V2
-            if program_id == "FEE2tBhCKAt7shrod19QttSVREUYPiyMzoku1mL1gqVK" || program_id == "FEEhPbKVKnco9EXnaY3i4R5rQVUx91wgVfu8qokixywi" {
-                let disc = if inst.data.len() >= 8 { hex::encode(&inst.data[..8]) } else { "none".to_string() };
-                
-                // Debug log for every Bags interaction
-                info!(
-                    "🔍 [SLOT {}] Bags Interaction | Program: {} | Disc: {} | Data Len: {}", 
-                    slot, 
-                    &program_id[..8], 
-                    disc,
-                    inst.data.len()
-                );
-
-                // Check if it's a CLAIM instruction
-                // claim_user = Creator/User withdrawing their fees (PRIMARY TARGET)
-                // claim_damm_v2/claim_dbc = Protocol distribution events (also relevant)
-                let is_claim = inst.data.starts_with(&claim_user) || 
-                              inst.data.starts_with(&claim_damm_v2) || 
-                              inst.data.starts_with(&claim_dbc);
-
-                if is_claim {
-                    let claim_type = if inst.data.starts_with(&claim_user) { "CLAIM_USER" }
-                                    else if inst.data.starts_with(&claim_damm_v2) { "DAMM_V2" }
-                                    else { "DBC" };
-                    info!(
-                        "🎯🎯🎯 CLAIM DETECTED! Type: {} | Sig: {}...", 
-                        claim_type,
-                        &sig[..10]
-                    );
-                    
-                    // Strategy A & B: Extract all accounts involved in this instruction
-                    let mut involved_accounts = HashSet::new();
-                    for &index in &inst.accounts {
-                        if let Some(key_bytes) = account_keys.get(index as usize) {
-                            involved_accounts.insert(bs58::encode(key_bytes).into_string());
-                        }
-                    }
-
-                    info!(
-                        "🎯 [SLOT {}] Bags CLAIM Matched! | Sig: {}... | Accounts: {}", 
-                        slot, 
-                        &sig[..10], 
-                        involved_accounts.len()
-                    );
-
-                    // Delegate to Manager to check all users
-                    self.manager.check_and_execute(&involved_accounts).await;
-                }
+            self.check_instruction(&ctx, inst.program_id_index, &inst.accounts, &inst.data, None).await;
+        }
+
+        // Inner instructions: claims wrapped by another program via CPI
+        // (multisigs, routers, custom claim bots)
+        let meta = match tx_info.meta.as_ref() {
+            Some(meta) => meta,
+            None => return,
+        };
+        for (invoker, inst) in wrapped_instructions(instructions, &account_keys, meta) {
+            self.check_instruction(&ctx, inst.program_id_index, &inst.accounts, &inst.data, Some(&invoker)).await;
+        }
+    }
+
+    /// Check a single (top-level or inner) instruction for a Bags claim
+    async fn check_instruction(
+        &self,
+        ctx: &TxContext<'_>,
+        program_id_index: u32,
+        accounts: &[u8],
+        data: &[u8],
+        invoked_by: Option<&str>,
+    ) {
+        let TxContext { slot, sig, account_keys, meta, source } = *ctx;
+
+        // Get the program ID for this instruction
+        let program_id = match account_keys.get(program_id_index as usize) {
+            Some(k) => bs58::encode(k).into_string(),
+            None => return,
+        };
+
+        // Monitor Bags Fee Share V1 & V2
+        let decoder = match FeeShareDecoder::for_program(&program_id) {
+            Some(decoder) => decoder,
+            None => return,
+        };
+        let name = decoder.instruction_name(data).unwrap_or("unknown");
+        let via = invoked_by.map(|p| format!(" | CPI via: {}", p)).unwrap_or_default();
+
+        // Debug log for every Bags interaction
+        info!(
+            "🔍 [SLOT {}] Bags Interaction | Program: {} | Ix: {} | Data Len: {}{}", 
+            slot, 
+            &program_id[..8], 
+            name,
+            data.len(),
+            via
+        );
+
+        let resolved_accounts: Vec<String> = accounts.iter()
+            .filter_map(|&index| account_keys.get(index as usize))
+            .map(|k| bs58::encode(k).into_string())
+            .collect();
+        if resolved_accounts.len() != accounts.len() {
+            return;
+        }
+
+        // claim_user (V1: claim_a/claim_b) = Creator/User withdrawing their fees (PRIMARY TARGET)
+        // claim_damm_v2/claim_dbc = Protocol distribution events (also relevant)
+        let claim = match decoder.decode_claim(data, &resolved_accounts) {
+            Some(c) => c,
+            None => return,
+        };
+
+        info!(
+            "🎯🎯🎯 CLAIM DETECTED! Type: {} | Sig: {}... | Source: {:?}{}", 
+            claim.kind(),
+            &sig[..10],
+            source,
+            via
+        );
+
+        let claimed_amount = meta.and_then(|m| claimed_amount(&claim, account_keys, m));
+
+        info!(
+            "🎯 [SLOT {}] Bags CLAIM Decoded! | Sig: {}... | {} | Claimed: {}", 
+            slot, 
+            &sig[..10], 
+            claim,
+            claimed_amount.map(|a| format!("{:.6}", a)).unwrap_or_else(|| "unknown".to_string())
+        );
+
+        let quote_mint = claim.quote_mint().to_string();
+        let detected = DetectedClaim {
+            claim,
+            signature: sig.to_string(),
+            slot,
+            source,
+            claimed_amount,
+            quote_mint,
+        };
+
+        // Delegate to Manager to check all users
+        self.manager.check_and_execute(&detected).await;
+    }
+}
+
+/// Bags Fee Share V1 or V2
+fn is_bags_program(program_id: &str) -> bool {
+    FeeShareDecoder::for_program(program_id).is_some()
+}
+
+/// Inner instructions to check, with the program that invoked each.
+/// Groups under a top-level Bags instruction are skipped: a Bags program
+/// calling itself is event emission, not a wrapped claim.
+fn wrapped_instructions<'a>(
+    instructions: &[CompiledInstruction],
+    account_keys: &[Vec<u8>],
+    meta: &'a TransactionStatusMeta,
+) -> Vec<(String, &'a InnerInstruction)> {
+    let mut wrapped = Vec::new();
+    for group in &meta.inner_instructions {
+        let invoker = match instructions.get(group.index as usize)
+            .and_then(|outer| account_keys.get(outer.program_id_index as usize))
+        {
+            Some(k) => bs58::encode(k).into_string(),
+            None => continue,
+        };
+        if is_bags_program(&invoker) {
+            continue;
+        }
+        wrapped.extend(group.instructions.iter().map(|inst| (invoker.clone(), inst)));
+    }
+    wrapped
+}
+
+/// Full account list for a message: static keys, then loaded writable, then
+/// loaded readonly addresses (the order instruction indexes refer to)
+fn full_account_keys(message: &Message, meta: Option<&TransactionStatusMeta>) -> Vec<Vec<u8>> {
+    let mut keys = message.account_keys.clone();
+    if let Some(meta) = meta {
+        keys.extend(meta.loaded_writable_addresses.iter().cloned());
+        keys.extend(meta.loaded_readonly_addresses.iter().cloned());
+    }
+    keys
+}
+
+/// How much quote the claim recipient received, from pre/post balances.
+/// Uses the recipient's quote token account; falls back to the recipient's
+/// lamport delta when the quote account was unwrapped or closed.
+fn claimed_amount(claim: &FeeShareClaim, account_keys: &[Vec<u8>], meta: &TransactionStatusMeta) -> Option<f64> {
+    let (recipient, quote_ata) = claim.recipient();
+    let index_of = |address: &str| {
+        let bytes = bs58::decode(address).into_vec().ok()?;
+        account_keys.iter().position(|k| *k == bytes)
+    };
+
+    if let Some(ata_index) = index_of(quote_ata) {
+        let balance = |balances: &[TokenBalance]| {
+            balances.iter()
+                .find(|b| b.account_index as usize == ata_index)
+                .and_then(|b| b.ui_token_amount.as_ref())
+                .map(|a| (a.amount.parse::<u64>().unwrap_or(0), a.decimals))
+        };
+        let pre = balance(&meta.pre_token_balances);
+        let post = balance(&meta.post_token_balances);
+        if let Some((post_amount, decimals)) = post {
+            let pre_amount = pre.map(|(a, _)| a).unwrap_or(0);
+            if post_amount > pre_amount {
+                return Some((post_amount - pre_amount) as f64 / 10f64.powi(decimals as i32));
             }
         }
     }
+
+    let recipient_index = index_of(recipient)?;
+    let pre = *meta.pre_balances.get(recipient_index)?;
+    let mut post = *meta.post_balances.get(recipient_index)?;
+    // The fee payer's delta is net of the transaction fee
+    if recipient_index == 0 {
+        post += meta.fee;
+    }
+    Some(post.saturating_sub(pre) as f64 / 1_000_000_000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yellowstone_grpc_proto::prelude::InnerInstructions;
+
+    const BAGS_V2: &str = "FEE2tBhCKAt7shrod19QttSVREUYPiyMzoku1mL1gqVK";
+    const ROUTER: &str = "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4";
+
+    fn key(seed: u8) -> Vec<u8> {
+        vec![seed; 32]
+    }
+
+    fn address(program_id: &str) -> Vec<u8> {
+        bs58::decode(program_id).into_vec().unwrap()
+    }
+
+    fn inner(data: u8) -> InnerInstruction {
+        InnerInstruction { program_id_index: 1, accounts: vec![0], data: vec![data], stack_height: Some(2) }
+    }
+
+    #[test]
+    fn v0_keys_append_loaded_writable_then_readonly() {
+        let message = Message { account_keys: vec![key(1), key(2), key(3)], ..Default::default() };
+        let meta = TransactionStatusMeta {
+            loaded_writable_addresses: vec![key(4), key(5)],
+            loaded_readonly_addresses: vec![key(6)],
+            ..Default::default()
+        };
+
+        let keys = full_account_keys(&message, Some(&meta));
+        assert_eq!(keys, [key(1), key(2), key(3), key(4), key(5), key(6)]);
+    }
+
+    #[test]
+    fn legacy_keys_are_the_static_keys() {
+        let message = Message { account_keys: vec![key(1), key(2)], ..Default::default() };
+        assert_eq!(full_account_keys(&message, None), [key(1), key(2)]);
+        assert_eq!(full_account_keys(&message, Some(&TransactionStatusMeta::default())), [key(1), key(2)]);
+    }
+
+    #[test]
+    fn walks_cpi_from_routers_but_not_from_bags() {
+        let account_keys = vec![key(1), address(BAGS_V2), address(ROUTER)];
+        let instructions = vec![
+            CompiledInstruction { program_id_index: 1, accounts: vec![0], data: vec![] },
+            CompiledInstruction { program_id_index: 2, accounts: vec![0], data: vec![] },
+        ];
+        let meta = TransactionStatusMeta {
+            inner_instructions: vec![
+                // Bags emitting its own event
+                InnerInstructions { index: 0, instructions: vec![inner(10)] },
+                // A router wrapping the claim
+                InnerInstructions { index: 1, instructions: vec![inner(20), inner(21)] },
+                // No such outer instruction
+                InnerInstructions { index: 5, instructions: vec![inner(30)] },
+            ],
+            ..Default::default()
+        };
+
+        let wrapped = wrapped_instructions(&instructions, &account_keys, &meta);
+        let seen: Vec<(&str, u8)> = wrapped.iter().map(|(invoker, inst)| (invoker.as_str(), inst.data[0])).collect();
+        assert_eq!(seen, [(ROUTER, 20), (ROUTER, 21)]);
+    }
 }
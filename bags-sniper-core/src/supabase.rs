@@ -5,10 +5,23 @@
 //! - Getting user watchlists and settings
 //! - Logging trades and activities
 //! - Updating sniper status
+//! - Streaming table changes over Supabase Realtime
 
 use reqwest::Client;
-use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
 use anyhow::{anyhow, Result};
+use crate::launch::DetectedLaunch;
+use crate::lifecycle::{SnipeCommitment, SnipeState, SnipeTransition};
+use futures::{SinkExt, StreamExt};
+use log::{info, warn};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Rows fetched per request when paging (PostgREST's default max-rows)
+const PAGE_SIZE: usize = 1000;
 
 #[derive(Clone)]
 pub struct SupabaseClient {
@@ -31,15 +44,201 @@ pub struct WatchlistItem {
     pub buy_amount: f64,
     pub is_active: bool,
     pub sniped: bool,
+    #[serde(default)]
+    pub trigger_policy: TriggerPolicy,
+    /// Minimum claimed quote amount (SOL) required to fire
+    #[serde(default)]
+    pub min_claim_amount: f64,
+    #[serde(default)]
+    pub trigger_mode: TriggerMode,
+    /// Minimum seconds between buys for repeat modes
+    #[serde(default)]
+    pub cooldown_secs: u64,
+    /// Fire on every Nth claim (every_nth mode)
+    #[serde(default = "default_trigger_every_n")]
+    pub trigger_every_n: u32,
+    /// Total buys allowed (max_buys mode)
+    #[serde(default = "default_max_buys")]
+    pub max_buys: u32,
+    #[serde(default)]
+    pub buy_mode: BuyMode,
+    /// Percent of wallet balance or claimed amount (balance_percent / claim_percent)
+    #[serde(default)]
+    pub buy_percent: f64,
+    /// Claim size tiers (tiered mode)
+    #[serde(default)]
+    pub buy_tiers: Vec<BuyTier>,
 }
 
+/// A creator (dev wallet) watched by a user: any `claim_user` it signs
+/// buys the claimed token
 #[derive(Debug, Deserialize, Clone)]
+pub struct CreatorWatchItem {
+    pub creator_address: String,
+    pub buy_amount: f64,
+    pub is_active: bool,
+    #[serde(default)]
+    pub min_claim_amount: f64,
+    #[serde(default)]
+    pub trigger_mode: TriggerMode,
+    #[serde(default)]
+    pub cooldown_secs: u64,
+    #[serde(default = "default_trigger_every_n")]
+    pub trigger_every_n: u32,
+    #[serde(default = "default_max_buys")]
+    pub max_buys: u32,
+    #[serde(default)]
+    pub buy_mode: BuyMode,
+    #[serde(default)]
+    pub buy_percent: f64,
+    #[serde(default)]
+    pub buy_tiers: Vec<BuyTier>,
+}
+
+/// A user's rule for auto-watching new Bags launches. Every set
+/// criterion must match; the buy settings are copied onto the
+/// watchlist entry it creates.
+#[derive(Debug, Deserialize, Clone)]
+pub struct LaunchRuleRow {
+    pub id: String,
+    /// Only launches by these creators (empty = any creator)
+    #[serde(default)]
+    pub creator_allowlist: Vec<String>,
+    /// Case-insensitive regex on the token name
+    #[serde(default)]
+    pub name_pattern: Option<String>,
+    /// Case-insensitive regex on the token symbol
+    #[serde(default)]
+    pub symbol_pattern: Option<String>,
+    /// Creator's initial buy bounds in SOL
+    #[serde(default)]
+    pub min_initial_buy: Option<f64>,
+    #[serde(default)]
+    pub max_initial_buy: Option<f64>,
+    pub buy_amount: f64,
+    #[serde(default)]
+    pub trigger_policy: TriggerPolicy,
+    #[serde(default)]
+    pub min_claim_amount: f64,
+    #[serde(default)]
+    pub trigger_mode: TriggerMode,
+    #[serde(default)]
+    pub cooldown_secs: u64,
+    #[serde(default = "default_trigger_every_n")]
+    pub trigger_every_n: u32,
+    #[serde(default = "default_max_buys")]
+    pub max_buys: u32,
+    #[serde(default)]
+    pub buy_mode: BuyMode,
+    #[serde(default)]
+    pub buy_percent: f64,
+    #[serde(default)]
+    pub buy_tiers: Vec<BuyTier>,
+}
+
+/// How a watchlist item sizes its buys
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BuyMode {
+    /// `buy_amount` SOL
+    #[default]
+    Fixed,
+    /// `buy_percent` of the wallet's free SOL balance
+    BalancePercent,
+    /// `buy_percent` of the SOL the claimer received
+    ClaimPercent,
+    /// `buy_tiers` keyed on the claimed amount
+    Tiered,
+}
+
+/// Buy `amount` SOL when at least `min_claim` SOL was claimed
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub struct BuyTier {
+    pub min_claim: f64,
+    pub amount: f64,
+}
+
+fn default_trigger_every_n() -> u32 {
+    1
+}
+
+fn default_max_buys() -> u32 {
+    1
+}
+
+/// How often a watched mint may fire
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerMode {
+    /// Buy on the first claim only
+    #[default]
+    First,
+    /// Buy on every claim, respecting the cooldown
+    Every,
+    /// Buy on every Nth claim
+    EveryNth,
+    /// Buy on claims until the total buy count is reached
+    MaxBuys,
+}
+
+/// Which claims on a watched mint may trigger a buy
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerPolicy {
+    /// Any claim touching the mint
+    #[default]
+    Any,
+    /// Only claims signed by the token's creator
+    Creator,
+    /// Only protocol or partner distributions not signed by the creator
+    Protocol,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct UserSettings {
     pub wallet_address: String,
     pub slippage: f64,
     pub priority_fee: f64,
     pub bribe: f64,
     pub telegram_user_id: Option<String>,
+    /// Skip backfilled claims older than this (None = act on any age)
+    #[serde(default = "default_max_backfill_age_secs")]
+    pub max_backfill_age_secs: Option<u64>,
+    /// Max SOL spent on a single token (None = no cap)
+    #[serde(default)]
+    pub max_buy_per_token: Option<f64>,
+    /// Max SOL spent in any rolling 24h window (None = no cap)
+    #[serde(default)]
+    pub max_daily_spend: Option<f64>,
+    /// Max SOL spent over the lifetime of the account (None = no cap)
+    #[serde(default)]
+    pub max_total_spend: Option<f64>,
+    /// Skip tokens whose mint authority is still set
+    #[serde(default)]
+    pub risk_block_mint_authority: bool,
+    /// Skip tokens with a freeze authority
+    #[serde(default)]
+    pub risk_block_freeze_authority: bool,
+    /// Skip Token-2022 tokens with a transfer fee
+    #[serde(default)]
+    pub risk_block_transfer_fee: bool,
+    /// Skip Token-2022 tokens with a transfer hook
+    #[serde(default)]
+    pub risk_block_transfer_hook: bool,
+    /// Skip tokens whose top holders own more than this percent of supply (None = no check)
+    #[serde(default)]
+    pub risk_max_top_holders_pct: Option<f64>,
+    /// Stop retrying a buy after this many seconds
+    #[serde(default = "default_buy_deadline_secs")]
+    pub buy_deadline_secs: u64,
+}
+
+fn default_max_backfill_age_secs() -> Option<u64> {
+    Some(30)
+}
+
+fn default_buy_deadline_secs() -> u64 {
+    20
 }
 
 impl Default for UserSettings {
@@ -50,10 +249,45 @@ impl Default for UserSettings {
             priority_fee: 0.0001,
             bribe: 0.0001,
             telegram_user_id: None,
+            max_backfill_age_secs: default_max_backfill_age_secs(),
+            max_buy_per_token: Some(1.0),
+            max_daily_spend: None,
+            max_total_spend: None,
+            risk_block_mint_authority: false,
+            risk_block_freeze_authority: false,
+            risk_block_transfer_fee: false,
+            risk_block_transfer_hook: false,
+            risk_max_top_holders_pct: None,
+            buy_deadline_secs: default_buy_deadline_secs(),
         }
     }
 }
 
+/// A past or in-flight buy of a mint by a wallet
+#[derive(Debug, Deserialize, Clone)]
+pub struct SnipeRecord {
+    pub wallet_address: String,
+    pub mint_address: String,
+    #[serde(alias = "sniped_at", alias = "created_at")]
+    pub at: Option<DateTime<Utc>>,
+    /// SOL spent (trade_logs only)
+    #[serde(default)]
+    pub amount_sol: Option<f64>,
+}
+
+/// Persisted lifecycle state of a (wallet, mint) snipe
+#[derive(Debug, Deserialize, Clone)]
+pub struct SnipeRow {
+    pub wallet_address: String,
+    pub mint_address: String,
+    pub state: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct InsertedRow {
+    id: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct UserData {
     pub wallet_address: String,
@@ -72,6 +306,11 @@ impl SupabaseClient {
         }
     }
 
+    /// Realtime client sharing this project's URL and key
+    pub fn realtime(&self) -> RealtimeClient {
+        RealtimeClient::new(&self.url, &self.key)
+    }
+
     fn api_url(&self, path: &str) -> String {
         format!("{}/rest/v1/{}", self.url, path)
     }
@@ -83,6 +322,34 @@ impl SupabaseClient {
         ]
     }
 
+    /// GET every row of an ordered query, a page at a time. PostgREST caps
+    /// a single response (1000 rows by default), so `url` must carry a
+    /// stable `order` for the offsets to line up.
+    async fn get_all_pages<T: DeserializeOwned>(&self, url: &str) -> Result<Vec<T>> {
+        let mut rows = Vec::new();
+        loop {
+            let page_url = format!("{}&limit={}&offset={}", url, PAGE_SIZE, rows.len());
+            let mut req = self.client.get(&page_url);
+            for (k, v) in self.auth_headers() {
+                req = req.header(k, v);
+            }
+
+            let res = req.send().await?;
+            if !res.status().is_success() {
+                let status = res.status();
+                let text = res.text().await.unwrap_or_default();
+                return Err(anyhow!("Supabase error {}: {}", status, text));
+            }
+
+            let page: Vec<T> = res.json().await?;
+            let done = page.len() < PAGE_SIZE;
+            rows.extend(page);
+            if done {
+                return Ok(rows);
+            }
+        }
+    }
+
     /// Get all users with is_running = true
     pub async fn get_active_users(&self) -> Result<Vec<ActiveUser>> {
         let url = format!("{}?is_running=eq.true&select=wallet_address,is_running", 
@@ -128,6 +395,98 @@ impl SupabaseClient {
         Ok(items)
     }
 
+    /// Get creator watchlist for a user
+    pub async fn get_user_creator_watchlist(&self, wallet: &str) -> Result<Vec<CreatorWatchItem>> {
+        let url = format!(
+            "{}?wallet_address=eq.{}&is_active=eq.true&select=*",
+            self.api_url("creator_watchlist"),
+            wallet
+        );
+        
+        let mut req = self.client.get(&url);
+        for (k, v) in self.auth_headers() {
+            req = req.header(k, v);
+        }
+        
+        let res = req.send().await?;
+        
+        if !res.status().is_success() {
+            return Err(anyhow!("Failed to get creator watchlist"));
+        }
+        
+        let items: Vec<CreatorWatchItem> = res.json().await?;
+        Ok(items)
+    }
+
+    /// Get launch auto-watch rules for a user
+    pub async fn get_user_launch_rules(&self, wallet: &str) -> Result<Vec<LaunchRuleRow>> {
+        let url = format!(
+            "{}?wallet_address=eq.{}&is_active=eq.true&select=*",
+            self.api_url("launch_rules"),
+            wallet
+        );
+        
+        let mut req = self.client.get(&url);
+        for (k, v) in self.auth_headers() {
+            req = req.header(k, v);
+        }
+        
+        let res = req.send().await?;
+        
+        if !res.status().is_success() {
+            return Err(anyhow!("Failed to get launch rules"));
+        }
+        
+        let rules: Vec<LaunchRuleRow> = res.json().await?;
+        Ok(rules)
+    }
+
+    /// Add a launched token to a user's watchlist on behalf of a launch rule.
+    /// Returns None if the user already has the mint.
+    pub async fn add_launch_watch(&self, wallet: &str, launch: &DetectedLaunch, rule: &LaunchRuleRow) -> Result<Option<WatchlistItem>> {
+        let url = format!("{}?on_conflict=wallet_address,mint_address", self.api_url("watchlist"));
+        
+        let body = serde_json::json!({
+            "wallet_address": wallet,
+            "mint_address": launch.mint,
+            "buy_amount": rule.buy_amount,
+            "is_active": true,
+            "trigger_policy": rule.trigger_policy,
+            "min_claim_amount": rule.min_claim_amount,
+            "trigger_mode": rule.trigger_mode,
+            "cooldown_secs": rule.cooldown_secs,
+            "trigger_every_n": rule.trigger_every_n,
+            "max_buys": rule.max_buys,
+            "buy_mode": rule.buy_mode,
+            "buy_percent": rule.buy_percent,
+            "buy_tiers": rule.buy_tiers,
+            "source": "launch_rule",
+            "launch_rule_id": rule.id,
+            "launch_signature": launch.signature
+        });
+        
+        let mut req = self.client.post(&url);
+        for (k, v) in self.auth_headers() {
+            req = req.header(k, v);
+        }
+        
+        let res = req
+            .header("Content-Type", "application/json")
+            .header("Prefer", "resolution=ignore-duplicates,return=representation")
+            .json(&body)
+            .send()
+            .await?;
+        
+        if !res.status().is_success() {
+            let status = res.status();
+            let text = res.text().await.unwrap_or_default();
+            return Err(anyhow!("Supabase error {}: {}", status, text));
+        }
+        
+        let rows: Vec<WatchlistItem> = res.json().await?;
+        Ok(rows.into_iter().next())
+    }
+
     /// Get user settings
     pub async fn get_user_settings(&self, wallet: &str) -> Result<UserSettings> {
         let url = format!(
@@ -206,6 +565,207 @@ impl SupabaseClient {
         Ok(())
     }
 
+    /// Watchlist items already marked as sniped
+    pub async fn get_sniped_watchlist(&self) -> Result<Vec<SnipeRecord>> {
+        let url = format!(
+            "{}?sniped=eq.true&select=wallet_address,mint_address,sniped_at&order=id.asc",
+            self.api_url("watchlist")
+        );
+        self.get_all_pages(&url).await
+            .map_err(|e| anyhow!("Failed to get sniped watchlist: {}", e))
+    }
+
+    /// Buys that succeeded or may have been sent (PENDING rows left by a crash)
+    pub async fn get_buy_history(&self) -> Result<Vec<SnipeRecord>> {
+        let url = format!(
+            "{}?action=eq.BUY&status=in.(SUCCESS,PENDING)&select=wallet_address,mint_address,amount_sol,created_at&order=created_at.asc,id.asc",
+            self.api_url("trade_logs")
+        );
+        self.get_all_pages(&url).await
+            .map_err(|e| anyhow!("Failed to get buy history: {}", e))
+    }
+
+    /// Insert a PENDING buy before the transaction is sent, returning the trade log id
+    pub async fn record_pending_buy(&self, wallet: &str, mint: &str, amount_sol: f64, sizing: &str, attempt: u32) -> Result<String> {
+        let url = self.api_url("trade_logs");
+        
+        let body = serde_json::json!({
+            "wallet_address": wallet,
+            "mint_address": mint,
+            "action": "BUY",
+            "amount_sol": amount_sol,
+            "sizing": sizing,
+            "attempt": attempt,
+            "status": "PENDING"
+        });
+        
+        let mut req = self.client.post(&url);
+        for (k, v) in self.auth_headers() {
+            req = req.header(k, v);
+        }
+        
+        let res = req
+            .header("Content-Type", "application/json")
+            .header("Prefer", "return=representation")
+            .json(&body)
+            .send()
+            .await?;
+        
+        if !res.status().is_success() {
+            let status = res.status();
+            let text = res.text().await.unwrap_or_default();
+            return Err(anyhow!("Supabase error {}: {}", status, text));
+        }
+        
+        let rows: Vec<InsertedRow> = res.json().await?;
+        rows.into_iter().next()
+            .map(|r| r.id)
+            .ok_or_else(|| anyhow!("Pending buy insert returned no row"))
+    }
+
+    /// Finalize a trade log row created by `record_pending_buy`
+    pub async fn update_trade(
+        &self,
+        trade_id: &str,
+        signature: Option<&str>,
+        status: &str,
+        error: Option<&str>,
+    ) -> Result<()> {
+        let url = format!("{}?id=eq.{}", self.api_url("trade_logs"), trade_id);
+        
+        let body = serde_json::json!({
+            "tx_signature": signature,
+            "status": status,
+            "error_message": error
+        });
+        
+        let mut req = self.client.patch(&url);
+        for (k, v) in self.auth_headers() {
+            req = req.header(k, v);
+        }
+        
+        let res = req
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .await?;
+        
+        if !res.status().is_success() {
+            return Err(anyhow!("Failed to update trade"));
+        }
+        
+        Ok(())
+    }
+
+    /// Current lifecycle state of every snipe
+    pub async fn get_snipes(&self) -> Result<Vec<SnipeRow>> {
+        let url = format!(
+            "{}?select=wallet_address,mint_address,state&order=id.asc",
+            self.api_url("snipes")
+        );
+        self.get_all_pages(&url).await
+            .map_err(|e| anyhow!("Failed to get snipes: {}", e))
+    }
+
+    /// Upsert a snipe's state, stamping the column for the new state
+    pub async fn upsert_snipe(&self, transition: &SnipeTransition) -> Result<()> {
+        let url = format!(
+            "{}?on_conflict=wallet_address,mint_address",
+            self.api_url("snipes")
+        );
+        
+        let at = transition.at.to_rfc3339();
+        let mut body = serde_json::json!({
+            "wallet_address": transition.wallet_address,
+            "mint_address": transition.mint_address,
+            "state": transition.state.as_str(),
+            "updated_at": at
+        });
+        body[format!("{}_at", transition.state.as_str())] = serde_json::json!(at);
+        if transition.state == SnipeState::ClaimDetected {
+            // A new cycle: clear the previous buy's outcome
+            body["buy_signature"] = serde_json::Value::Null;
+            body["error_message"] = serde_json::Value::Null;
+            body["tx_sent_at"] = serde_json::Value::Null;
+            body["confirmed_at"] = serde_json::Value::Null;
+            body["failed_at"] = serde_json::Value::Null;
+            body["buy_commitment"] = serde_json::Value::Null;
+            body["processed_at"] = serde_json::Value::Null;
+            body["finalized_at"] = serde_json::Value::Null;
+            body["expired_at"] = serde_json::Value::Null;
+        }
+        if let Some(sig) = &transition.claim_signature {
+            body["claim_signature"] = serde_json::json!(sig);
+        }
+        if let Some(sig) = &transition.buy_signature {
+            body["buy_signature"] = serde_json::json!(sig);
+        }
+        if let Some(error) = &transition.error {
+            body["error_message"] = serde_json::json!(error);
+        }
+        
+        let mut req = self.client.post(&url);
+        for (k, v) in self.auth_headers() {
+            req = req.header(k, v);
+        }
+        
+        let res = req
+            .header("Content-Type", "application/json")
+            .header("Prefer", "resolution=merge-duplicates")
+            .json(&body)
+            .send()
+            .await?;
+        
+        if !res.status().is_success() {
+            let status = res.status();
+            let text = res.text().await.unwrap_or_default();
+            return Err(anyhow!("Supabase error {}: {}", status, text));
+        }
+        
+        Ok(())
+    }
+
+    /// Record the commitment a snipe's current buy reached
+    pub async fn update_snipe_commitment(&self, commitment: &SnipeCommitment) -> Result<()> {
+        let url = format!(
+            "{}?wallet_address=eq.{}&mint_address=eq.{}&buy_signature=eq.{}",
+            self.api_url("snipes"),
+            commitment.wallet_address,
+            commitment.mint_address,
+            commitment.buy_signature
+        );
+
+        let at = commitment.at.to_rfc3339();
+        let status = commitment.status.as_str();
+        let mut body = serde_json::json!({
+            "buy_commitment": status,
+            "updated_at": at
+        });
+        // confirmed_at and failed_at belong to the lifecycle states
+        if matches!(status, "processed" | "finalized" | "expired") {
+            body[format!("{}_at", status)] = serde_json::json!(at);
+        }
+
+        let mut req = self.client.patch(&url);
+        for (k, v) in self.auth_headers() {
+            req = req.header(k, v);
+        }
+
+        let res = req
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .await?;
+
+        if !res.status().is_success() {
+            let status = res.status();
+            let text = res.text().await.unwrap_or_default();
+            return Err(anyhow!("Supabase error {}: {}", status, text));
+        }
+
+        Ok(())
+    }
+
     /// Log activity
     pub async fn log_activity(&self, wallet: &str, log_type: &str, message: &str) -> Result<()> {
         let url = self.api_url("activity_logs");
@@ -290,3 +850,194 @@ impl SupabaseClient {
         Ok(())
     }
 }
+
+// ============================================================================
+// REALTIME
+// ============================================================================
+
+/// Phoenix heartbeat interval expected by Supabase Realtime
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(25);
+/// Reconnect if nothing (not even a heartbeat reply) arrives for this long
+const REALTIME_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+const REALTIME_TOPIC: &str = "realtime:bags-sniper";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ChangeKind {
+    Insert,
+    Update,
+    Delete,
+}
+
+/// A row change on a subscribed table
+#[derive(Debug, Clone, Deserialize)]
+pub struct RealtimeChange {
+    pub table: String,
+    #[serde(rename = "type")]
+    pub kind: ChangeKind,
+    /// New row (empty for DELETE)
+    #[serde(default)]
+    pub record: serde_json::Value,
+    /// Previous row; only the primary key unless the table has REPLICA IDENTITY FULL
+    #[serde(default)]
+    pub old_record: serde_json::Value,
+}
+
+impl RealtimeChange {
+    /// The current row, or the old one for DELETE
+    pub fn row(&self) -> &serde_json::Value {
+        match self.kind {
+            ChangeKind::Delete => &self.old_record,
+            _ => &self.record,
+        }
+    }
+
+    pub fn wallet_address(&self) -> Option<&str> {
+        self.row().get("wallet_address").and_then(|v| v.as_str())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum RealtimeEvent {
+    /// Channel joined; changes before this point may have been missed
+    Subscribed,
+    Change(RealtimeChange),
+}
+
+#[derive(Debug, Deserialize)]
+struct PhoenixMessage {
+    event: String,
+    #[serde(default)]
+    payload: serde_json::Value,
+}
+
+/// Supabase Realtime client (Phoenix channels over WebSocket)
+pub struct RealtimeClient {
+    ws_url: String,
+    key: String,
+}
+
+impl RealtimeClient {
+    pub fn new(url: &str, key: &str) -> Self {
+        let ws_base = url.trim_end_matches('/')
+            .replacen("https://", "wss://", 1)
+            .replacen("http://", "ws://", 1);
+        Self {
+            ws_url: format!("{}/realtime/v1/websocket?apikey={}&vsn=1.0.0", ws_base, key),
+            key: key.to_string(),
+        }
+    }
+
+    /// Stream INSERT/UPDATE/DELETE on `tables` (public schema) forever,
+    /// reconnecting with backoff. Returns once the receiver is dropped.
+    pub async fn run(self, tables: &[&str], tx: mpsc::UnboundedSender<RealtimeEvent>) {
+        let mut backoff = Duration::from_secs(1);
+        loop {
+            match self.session(tables, &tx).await {
+                Ok(()) => return,
+                Err(e) => warn!("⚠️ Realtime connection lost: {} (reconnecting in {}s)", e, backoff.as_secs()),
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(Duration::from_secs(30));
+        }
+    }
+
+    /// One connection: join, then forward changes until it drops.
+    /// Ok(()) means the receiver went away and we should stop.
+    async fn session(&self, tables: &[&str], tx: &mpsc::UnboundedSender<RealtimeEvent>) -> Result<()> {
+        let (ws, _) = tokio_tungstenite::connect_async(self.ws_url.as_str()).await?;
+        let (mut sink, mut stream) = ws.split();
+
+        let changes: Vec<serde_json::Value> = tables.iter()
+            .map(|t| serde_json::json!({ "event": "*", "schema": "public", "table": t }))
+            .collect();
+        let join = serde_json::json!({
+            "topic": REALTIME_TOPIC,
+            "event": "phx_join",
+            "payload": {
+                "config": {
+                    "broadcast": { "self": false },
+                    "presence": { "key": "" },
+                    "postgres_changes": changes
+                },
+                "access_token": self.key
+            },
+            "ref": "1",
+            "join_ref": "1"
+        });
+        sink.send(Message::Text(join.to_string())).await?;
+
+        let mut next_ref: u64 = 2;
+        let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+        heartbeat.tick().await;
+        let mut last_message = tokio::time::Instant::now();
+
+        loop {
+            tokio::select! {
+                _ = heartbeat.tick() => {
+                    let beat = serde_json::json!({
+                        "topic": "phoenix",
+                        "event": "heartbeat",
+                        "payload": {},
+                        "ref": next_ref.to_string()
+                    });
+                    next_ref += 1;
+                    sink.send(Message::Text(beat.to_string())).await?;
+                }
+                _ = tokio::time::sleep_until(last_message + REALTIME_IDLE_TIMEOUT) => {
+                    return Err(anyhow!("no messages for {}s", REALTIME_IDLE_TIMEOUT.as_secs()));
+                }
+                msg = stream.next() => {
+                    let text = match msg {
+                        Some(Ok(Message::Text(text))) => text,
+                        Some(Ok(Message::Ping(data))) => {
+                            sink.send(Message::Pong(data)).await?;
+                            continue;
+                        }
+                        Some(Ok(Message::Close(frame))) => return Err(anyhow!("closed by server: {:?}", frame)),
+                        Some(Ok(_)) => continue,
+                        Some(Err(e)) => return Err(e.into()),
+                        None => return Err(anyhow!("stream ended")),
+                    };
+                    last_message = tokio::time::Instant::now();
+
+                    let message: PhoenixMessage = match serde_json::from_str(&text) {
+                        Ok(m) => m,
+                        Err(e) => {
+                            warn!("⚠️ Unparseable realtime message: {}", e);
+                            continue;
+                        }
+                    };
+
+                    let event = match message.event.as_str() {
+                        "phx_reply" if message.payload["response"].get("postgres_changes").is_some() => {
+                            if message.payload["status"] != "ok" {
+                                return Err(anyhow!("join rejected: {}", message.payload["response"]));
+                            }
+                            info!("📡 Realtime subscribed to {}", tables.join(", "));
+                            RealtimeEvent::Subscribed
+                        }
+                        "postgres_changes" => {
+                            match serde_json::from_value::<RealtimeChange>(message.payload["data"].clone()) {
+                                Ok(change) => RealtimeEvent::Change(change),
+                                Err(e) => {
+                                    warn!("⚠️ Unparseable realtime change: {}", e);
+                                    continue;
+                                }
+                            }
+                        }
+                        "phx_error" | "phx_close" => return Err(anyhow!("channel {}: {}", message.event, message.payload)),
+                        "system" if message.payload["status"] == "error" => {
+                            return Err(anyhow!("realtime error: {}", message.payload["message"]));
+                        }
+                        _ => continue,
+                    };
+
+                    if tx.send(event).is_err() {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+}
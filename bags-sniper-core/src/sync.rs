@@ -0,0 +1,383 @@
+//! User Sync for Bags Sniper
+//!
+//! Keeps `SniperManager` in step with Supabase. Realtime changes on
+//! `watchlist`, `creator_watchlist`, `launch_rules`, `user_settings` and
+//! `sniper_status` are applied as they arrive; wallet key changes arrive
+//! as a `sniper_status.key_version` bump and the key is re-fetched over
+//! REST, so `users` (which holds the keys) is never streamed.
+//! `refresh_users` is the full diff-based reconciliation run on
+//! (re)subscribe and on a slow timer as a safety net.
+
+use crate::encryption;
+use crate::launch::LaunchRule;
+use crate::manager::{SniperManager, WatchEntry};
+use crate::supabase::{ChangeKind, CreatorWatchItem, LaunchRuleRow, RealtimeChange, RealtimeEvent, SupabaseClient, UserSettings, WatchlistItem};
+use anyhow::{anyhow, Result};
+use log::{error, info, warn};
+use std::collections::HashSet;
+use std::env;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Tables whose changes affect running users
+pub const REALTIME_TABLES: &[&str] = &["watchlist", "creator_watchlist", "launch_rules", "user_settings", "sniper_status"];
+
+/// Diff the running users against Supabase. Users whose data could not
+/// be fetched this round keep their current state; users that stopped
+/// (or have no wallet key) are removed.
+pub async fn refresh_users(supabase: &SupabaseClient, manager: &SniperManager) -> Result<()> {
+    let active_users = supabase.get_active_users().await?;
+    let mut keep: HashSet<String> = HashSet::new();
+    
+    for user in active_users {
+        match sync_user(supabase, manager, &user.wallet_address).await {
+            Ok(true) => {
+                keep.insert(user.wallet_address);
+            },
+            Ok(false) => {
+                // User has no private key, skip
+            },
+            Err(e) => {
+                keep.insert(user.wallet_address.clone());
+                error!("❌ Error syncing {}: {}", &user.wallet_address[..8], e);
+            }
+        }
+    }
+
+    manager.retain_users(&keep);
+    Ok(())
+}
+
+/// Fetch one user's key, settings and watchlist and sync them into the
+/// manager. Returns false if the user has no wallet key.
+async fn sync_user(supabase: &SupabaseClient, manager: &SniperManager, wallet: &str) -> Result<bool> {
+    let (watchlist, creator_watchlist, launch_rules, settings, pk) = tokio::join!(
+        supabase.get_user_watchlist(wallet),
+        supabase.get_user_creator_watchlist(wallet),
+        supabase.get_user_launch_rules(wallet),
+        supabase.get_user_settings(wallet),
+        supabase.get_user_private_key(wallet)
+    );
+
+    let stored_key = match pk? {
+        Some(key) => key,
+        None => return Ok(false),
+    };
+
+    // Decrypt the private key if encrypted
+    let encryption_key = env::var("ENCRYPTION_KEY").unwrap_or_else(|_| "22e83f82f9bacb57ccb213476ff60db9de7be2e8d0d41cf1e6704265ccc626fd".to_string());
+    let private_key = encryption::decrypt_private_key(&stored_key, &encryption_key)
+        .map_err(|e| anyhow!("Failed to decrypt private key: {}", e))?;
+
+    let entries = watchlist?.iter()
+        .map(|item| (item.mint_address.clone(), WatchEntry::from(item)))
+        .collect();
+    let creator_entries = creator_watchlist?.iter()
+        .map(|item| (item.creator_address.clone(), WatchEntry::from(item)))
+        .collect();
+    let launch_rules = launch_rules?;
+    manager.sync_user(wallet, private_key, settings?, entries, creator_entries);
+    manager.set_launch_rules(&wallet.to_string(), compile_rules(wallet, launch_rules)).ok();
+    Ok(true)
+}
+
+/// Compile launch rules, dropping (and logging) any with a bad pattern
+fn compile_rules(wallet: &str, rows: Vec<LaunchRuleRow>) -> Vec<LaunchRule> {
+    rows.into_iter()
+        .filter_map(|row| {
+            let id = row.id.clone();
+            LaunchRule::compile(row)
+                .map_err(|e| warn!("⚠️ Skipping launch rule {} of {}: {}", id, wallet, e))
+                .ok()
+        })
+        .collect()
+}
+
+/// Subscribe to Supabase Realtime and apply changes until the process exits
+pub async fn run_realtime(supabase: Arc<SupabaseClient>, manager: Arc<SniperManager>) {
+    let (tx, mut events) = mpsc::unbounded_channel();
+    tokio::spawn(supabase.realtime().run(REALTIME_TABLES, tx));
+
+    while let Some(event) = events.recv().await {
+        match event {
+            RealtimeEvent::Subscribed => {
+                // Catch up on anything missed while disconnected
+                if let Err(e) = refresh_users(&supabase, &manager).await {
+                    error!("User refresh failed: {}", e);
+                }
+            },
+            RealtimeEvent::Change(change) => {
+                if let Err(e) = apply_change(&supabase, &manager, &change).await {
+                    warn!("⚠️ Could not apply {:?} on {}: {} (running full refresh)", change.kind, change.table, e);
+                    if let Err(e) = refresh_users(&supabase, &manager).await {
+                        error!("User refresh failed: {}", e);
+                    }
+                }
+            },
+        }
+    }
+}
+
+/// Apply one row change to the manager
+async fn apply_change(supabase: &SupabaseClient, manager: &SniperManager, change: &RealtimeChange) -> Result<()> {
+    let wallet = change.wallet_address()
+        .ok_or_else(|| anyhow!("row has no wallet_address (is REPLICA IDENTITY FULL set?)"))?
+        .to_string();
+
+    match (change.table.as_str(), change.kind) {
+        ("watchlist", ChangeKind::Delete) => {
+            let mint = change.old_record["mint_address"].as_str()
+                .ok_or_else(|| anyhow!("deleted row has no mint_address"))?;
+            manager.remove_from_watchlist(&wallet, &mint.to_string()).ok();
+        },
+        ("watchlist", _) => {
+            let item: WatchlistItem = serde_json::from_value(change.record.clone())?;
+            if let Some(old_mint) = change.old_record["mint_address"].as_str() {
+                if old_mint != item.mint_address {
+                    manager.remove_from_watchlist(&wallet, &old_mint.to_string()).ok();
+                }
+            }
+            if item.is_active {
+                manager.add_to_watchlist(&wallet, item.mint_address.clone(), WatchEntry::from(&item)).ok();
+            } else {
+                manager.remove_from_watchlist(&wallet, &item.mint_address).ok();
+            }
+        },
+        ("creator_watchlist", ChangeKind::Delete) => {
+            let creator = change.old_record["creator_address"].as_str()
+                .ok_or_else(|| anyhow!("deleted row has no creator_address"))?;
+            manager.remove_creator_watch(&wallet, &creator.to_string()).ok();
+        },
+        ("creator_watchlist", _) => {
+            let item: CreatorWatchItem = serde_json::from_value(change.record.clone())?;
+            if let Some(old_creator) = change.old_record["creator_address"].as_str() {
+                if old_creator != item.creator_address {
+                    manager.remove_creator_watch(&wallet, &old_creator.to_string()).ok();
+                }
+            }
+            if item.is_active {
+                manager.add_creator_watch(&wallet, item.creator_address.clone(), WatchEntry::from(&item)).ok();
+            } else {
+                manager.remove_creator_watch(&wallet, &item.creator_address).ok();
+            }
+        },
+        ("launch_rules", _) => {
+            // Rules are matched as a set, reload them all
+            if manager.has_user(&wallet) {
+                let rows = supabase.get_user_launch_rules(&wallet).await?;
+                manager.set_launch_rules(&wallet, compile_rules(&wallet, rows)).ok();
+            }
+        },
+        ("user_settings", ChangeKind::Delete) => {
+            manager.update_settings(&wallet, UserSettings::default()).ok();
+        },
+        ("user_settings", _) => {
+            let settings: UserSettings = serde_json::from_value(change.record.clone())?;
+            manager.update_settings(&wallet, settings).ok();
+        },
+        ("sniper_status", ChangeKind::Delete) => {
+            manager.remove_user(&wallet);
+        },
+        ("sniper_status", _) => {
+            // Started, or key_version bumped: re-fetch key, settings and watchlists
+            if change.record["is_running"].as_bool() == Some(true) {
+                if !sync_user(supabase, manager, &wallet).await? {
+                    info!("User {} started without a wallet key, skipping", wallet);
+                }
+            } else {
+                manager.remove_user(&wallet);
+            }
+        },
+        (table, _) => return Err(anyhow!("unexpected table {}", table)),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{SinkExt, StreamExt};
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_tungstenite::tungstenite::Message;
+
+    const WALLET: &str = "wallet1";
+
+    /// Rows served over REST, keyed by table
+    type Tables = Arc<Mutex<HashMap<&'static str, Value>>>;
+
+    /// Supabase stand-in on one port: `/realtime` upgrades to a Phoenix
+    /// socket that acks the join and then relays `pushes`; anything else is
+    /// a PostgREST GET answered with the rows of its table (or `[]`)
+    async fn mock_supabase(tables: Tables) -> (String, mpsc::UnboundedSender<Value>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (pushes, rx) = mpsc::unbounded_channel();
+        let rx = Arc::new(tokio::sync::Mutex::new(rx));
+        tokio::spawn(async move {
+            loop {
+                let (socket, _) = match listener.accept().await {
+                    Ok(conn) => conn,
+                    Err(_) => return,
+                };
+                let mut head = [0u8; 32];
+                let n = socket.peek(&mut head).await.unwrap();
+                if head[..n].starts_with(b"GET /realtime") {
+                    tokio::spawn(serve_phoenix(socket, rx.clone()));
+                } else {
+                    tokio::spawn(serve_rest(socket, tables.clone()));
+                }
+            }
+        });
+        (url, pushes)
+    }
+
+    async fn serve_phoenix(socket: TcpStream, pushes: Arc<tokio::sync::Mutex<mpsc::UnboundedReceiver<Value>>>) {
+        let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+        let join: Value = match ws.next().await {
+            Some(Ok(Message::Text(text))) => serde_json::from_str(&text).unwrap(),
+            other => panic!("expected phx_join, got {:?}", other),
+        };
+        assert_eq!(join["event"], "phx_join");
+        let tables: Vec<&str> = join["payload"]["config"]["postgres_changes"].as_array().unwrap()
+            .iter()
+            .filter_map(|c| c["table"].as_str())
+            .collect();
+        assert_eq!(tables, REALTIME_TABLES);
+        let reply = json!({
+            "topic": join["topic"],
+            "event": "phx_reply",
+            "payload": { "status": "ok", "response": { "postgres_changes": join["payload"]["config"]["postgres_changes"] } },
+            "ref": join["ref"]
+        });
+        ws.send(Message::Text(reply.to_string())).await.unwrap();
+
+        let mut pushes = pushes.lock().await;
+        loop {
+            tokio::select! {
+                push = pushes.recv() => match push {
+                    Some(data) => {
+                        let message = json!({
+                            "topic": join["topic"],
+                            "event": "postgres_changes",
+                            "payload": { "data": data, "ids": [1] },
+                            "ref": null
+                        });
+                        ws.send(Message::Text(message.to_string())).await.unwrap();
+                    }
+                    None => return,
+                },
+                msg = ws.next() => if msg.is_none() {
+                    return;
+                },
+            }
+        }
+    }
+
+    async fn serve_rest(mut socket: TcpStream, tables: Tables) {
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 4096];
+        let body_start = loop {
+            let n = socket.read(&mut chunk).await.unwrap();
+            if n == 0 {
+                return;
+            }
+            buf.extend_from_slice(&chunk[..n]);
+            if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                break pos + 4;
+            }
+        };
+        let head = String::from_utf8_lossy(&buf[..body_start]).to_string();
+        let length: usize = head.to_lowercase().lines()
+            .find_map(|l| l.strip_prefix("content-length:").map(str::to_string))
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(0);
+        while buf.len() < body_start + length {
+            let n = socket.read(&mut chunk).await.unwrap();
+            buf.extend_from_slice(&chunk[..n]);
+        }
+        let path = head.split_whitespace().nth(1).unwrap_or("");
+        let table = path.trim_start_matches("/rest/v1/").split('?').next().unwrap_or("");
+        let body = tables.lock().unwrap().get(table).cloned().unwrap_or_else(|| json!([])).to_string();
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        socket.write_all(response.as_bytes()).await.ok();
+    }
+
+    fn watchlist_row(mint: &str) -> Value {
+        json!({
+            "id": format!("row-{}", mint),
+            "wallet_address": WALLET,
+            "mint_address": mint,
+            "buy_amount": 0.1,
+            "is_active": true,
+            "sniped": false
+        })
+    }
+
+    fn change(table: &str, kind: &str, record: Value, old_record: Value) -> Value {
+        json!({ "table": table, "type": kind, "record": record, "old_record": old_record })
+    }
+
+    /// Poll until `check` holds, failing after a few seconds
+    async fn eventually(what: &str, check: impl Fn() -> bool) {
+        for _ in 0..250 {
+            if check() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("timed out waiting for {}", what);
+    }
+
+    fn watchlist(manager: &SniperManager) -> Vec<String> {
+        let mut mints = manager.get_watchlist(&WALLET.to_string()).unwrap_or_default();
+        mints.sort();
+        mints
+    }
+
+    #[tokio::test]
+    async fn realtime_changes_reach_the_manager() {
+        let tables: Tables = Arc::new(Mutex::new(HashMap::from([
+            ("users", json!([{ "wallet_address": WALLET, "encrypted_private_key": "key-v1" }])),
+        ])));
+        let (url, pushes) = mock_supabase(tables.clone()).await;
+        let supabase = Arc::new(SupabaseClient::new(url, "anon".to_string()));
+        let manager = Arc::new(SniperManager::new("http://127.0.0.1:1".to_string(), supabase.clone(), None));
+        tokio::spawn(run_realtime(supabase, manager.clone()));
+
+        let status = |version: u64| change(
+            "sniper_status",
+            "UPDATE",
+            json!({ "wallet_address": WALLET, "is_running": true, "key_version": version }),
+            json!({ "wallet_address": WALLET }),
+        );
+
+        // Started: the user is loaded over REST
+        pushes.send(status(1)).unwrap();
+        eventually("user to start", || manager.has_user(&WALLET.to_string())).await;
+        assert!(watchlist(&manager).is_empty());
+
+        pushes.send(change("watchlist", "INSERT", watchlist_row("mintA"), json!({}))).unwrap();
+        eventually("watchlist insert", || watchlist(&manager) == ["mintA"]).await;
+
+        pushes.send(change("watchlist", "DELETE", json!({}), json!({ "wallet_address": WALLET, "mint_address": "mintA" }))).unwrap();
+        eventually("watchlist delete", || watchlist(&manager).is_empty()).await;
+
+        // A key_version bump re-fetches key, settings and watchlists
+        {
+            let mut tables = tables.lock().unwrap();
+            tables.insert("users", json!([{ "wallet_address": WALLET, "encrypted_private_key": "key-v2" }]));
+            tables.insert("watchlist", json!([watchlist_row("mintB")]));
+        }
+        pushes.send(status(2)).unwrap();
+        eventually("key_version bump", || watchlist(&manager) == ["mintB"]).await;
+    }
+}
@@ -0,0 +1,328 @@
+//! Trigger evaluation for watched mints
+//!
+//! Decides whether a detected claim should fire a buy for a watchlist
+//! item: trigger policy, claim size, and the trigger mode with its
+//! per-(user, mint) counters and cooldowns. Also sizes the buy.
+
+use crate::fee_share::FeeShareClaim;
+use crate::launch::WSOL_MINT;
+use crate::supabase::{BuyMode, BuyTier, TriggerMode, TriggerPolicy};
+use std::time::{Duration, Instant};
+
+/// Trigger mode settings of a watchlist item
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TriggerRule {
+    pub mode: TriggerMode,
+    /// Minimum time between buys for repeat modes
+    pub cooldown: Duration,
+    /// Fire on every Nth eligible claim (EveryNth)
+    pub every_n: u32,
+    /// Total buys allowed (MaxBuys)
+    pub max_buys: u32,
+}
+
+/// Counters for one (user, mint) pair
+#[derive(Debug, Clone, Default)]
+pub struct TriggerState {
+    /// Eligible claims seen (passed policy and size filters)
+    pub claims_seen: u64,
+    pub buys: u32,
+    pub last_buy_at: Option<Instant>,
+    /// Claim signature that last fired, so one transaction never buys twice
+    pub last_claim_sig: Option<String>,
+}
+
+impl TriggerState {
+    /// Count an eligible claim and decide whether it fires.
+    /// On success the buy is recorded; on rejection the reason is returned.
+    pub fn evaluate(&mut self, rule: &TriggerRule, claim_sig: &str) -> Result<(), String> {
+        if self.last_claim_sig.as_deref() == Some(claim_sig) {
+            return Err("already fired for this claim transaction".to_string());
+        }
+
+        self.claims_seen += 1;
+
+        match rule.mode {
+            TriggerMode::First if self.buys > 0 => return Err("first-claim mode already bought".to_string()),
+            TriggerMode::EveryNth => {
+                let n = rule.every_n.max(1) as u64;
+                if !self.claims_seen.is_multiple_of(n) {
+                    return Err(format!("claim {} of every {}", self.claims_seen % n, n));
+                }
+            }
+            TriggerMode::MaxBuys if self.buys >= rule.max_buys => {
+                return Err(format!("reached max of {} buys", rule.max_buys));
+            }
+            _ => {}
+        }
+
+        if rule.mode != TriggerMode::First {
+            if let Some(last) = self.last_buy_at {
+                let elapsed = last.elapsed();
+                if elapsed < rule.cooldown {
+                    return Err(format!(
+                        "cooldown: {}s since last buy, need {}s",
+                        elapsed.as_secs(),
+                        rule.cooldown.as_secs()
+                    ));
+                }
+            }
+        }
+
+        self.buys += 1;
+        self.last_buy_at = Some(Instant::now());
+        self.last_claim_sig = Some(claim_sig.to_string());
+        Ok(())
+    }
+
+    /// Undo the buy recorded by a successful `evaluate` that was then
+    /// rejected downstream. The claim itself stays counted.
+    pub fn revert_buy(&mut self, before: &TriggerState) {
+        self.buys = before.buys;
+        self.last_buy_at = before.last_buy_at;
+    }
+}
+
+impl TriggerState {
+    /// Rebuild counters from persisted buys (`ages` since each buy).
+    /// A sniped flag without trade rows still counts as one buy.
+    pub fn from_history(ages: &[Duration], sniped: bool) -> Self {
+        let buys = (ages.len() as u32).max(sniped as u32);
+        let now = Instant::now();
+        let last_buy_at = ages.iter()
+            .min()
+            .map(|age| now.checked_sub(*age).unwrap_or(now))
+            .or(if sniped { Some(now) } else { None });
+        Self {
+            claims_seen: buys as u64,
+            buys,
+            last_buy_at,
+            last_claim_sig: None,
+        }
+    }
+}
+
+/// Decide whether a claim satisfies a watchlist item's trigger policy.
+/// Returns the skip reason on rejection.
+pub fn check_trigger_policy(policy: TriggerPolicy, claim: &FeeShareClaim, creator: Option<&String>) -> Result<(), String> {
+    if policy == TriggerPolicy::Any {
+        return Ok(());
+    }
+
+    let creator = match creator {
+        Some(c) => c,
+        None => return Err(format!("{:?} policy but creator is not resolved yet", policy)),
+    };
+    let creator_signed = claim.signers().iter().any(|s| *s == creator);
+
+    match policy {
+        TriggerPolicy::Any => Ok(()),
+        TriggerPolicy::Creator if creator_signed => Ok(()),
+        TriggerPolicy::Creator => Err(format!("claim not signed by creator {}", creator)),
+        TriggerPolicy::Protocol if claim.is_distribution() && !creator_signed => Ok(()),
+        TriggerPolicy::Protocol => Err("not a protocol or partner distribution".to_string()),
+    }
+}
+
+/// Reject claims below the watchlist item's minimum claimed amount
+pub fn check_claim_size(min_claim_amount: f64, claimed_amount: Option<f64>) -> Result<(), String> {
+    if min_claim_amount <= 0.0 {
+        return Ok(());
+    }
+    match claimed_amount {
+        Some(amount) if amount >= min_claim_amount => Ok(()),
+        Some(amount) => Err(format!("claimed {:.6} below minimum {:.6}", amount, min_claim_amount)),
+        None => Err(format!("claimed amount unknown, minimum is {:.6}", min_claim_amount)),
+    }
+}
+
+/// Buy sizing strategy of a watchlist item
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuySizing {
+    Fixed(f64),
+    BalancePercent(f64),
+    ClaimPercent(f64),
+    /// Sorted by `min_claim`, ascending
+    Tiered(Vec<BuyTier>),
+}
+
+impl BuySizing {
+    pub fn new(mode: BuyMode, buy_amount: f64, percent: f64, tiers: &[BuyTier]) -> Self {
+        match mode {
+            BuyMode::Fixed => BuySizing::Fixed(buy_amount),
+            BuyMode::BalancePercent => BuySizing::BalancePercent(percent),
+            BuyMode::ClaimPercent => BuySizing::ClaimPercent(percent),
+            BuyMode::Tiered => {
+                let mut tiers = tiers.to_vec();
+                tiers.sort_by(|a, b| a.min_claim.total_cmp(&b.min_claim));
+                BuySizing::Tiered(tiers)
+            }
+        }
+    }
+
+    /// SOL to buy and a description of how it was sized, or the skip reason.
+    /// `balance` is the wallet's free SOL; `claimed` the quote the claimer
+    /// received, in `quote_mint`. Claim-based sizing only works for SOL claims.
+    pub fn amount(&self, balance: Option<f64>, claimed: Option<f64>, quote_mint: &str) -> Result<(f64, String), String> {
+        if matches!(self, BuySizing::ClaimPercent(_) | BuySizing::Tiered(_)) && quote_mint != WSOL_MINT {
+            return Err(format!("claim is paid in {}, not SOL", quote_mint));
+        }
+        let (amount, how) = match self {
+            BuySizing::Fixed(amount) => (*amount, "fixed".to_string()),
+            BuySizing::BalancePercent(percent) => {
+                let balance = balance.ok_or("wallet balance unknown")?;
+                (balance * percent / 100.0, format!("{}% of {:.4} SOL balance", percent, balance))
+            }
+            BuySizing::ClaimPercent(percent) => {
+                let claimed = claimed.ok_or("claimed amount unknown")?;
+                (claimed * percent / 100.0, format!("{}% of {:.4} SOL claimed", percent, claimed))
+            }
+            BuySizing::Tiered(tiers) => {
+                let claimed = claimed.ok_or("claimed amount unknown")?;
+                let tier = tiers.iter()
+                    .rev()
+                    .find(|t| claimed >= t.min_claim)
+                    .ok_or_else(|| format!("claim of {:.4} SOL below the lowest tier", claimed))?;
+                (tier.amount, format!("tier >= {} SOL for {:.4} SOL claimed", tier.min_claim, claimed))
+            }
+        };
+
+        if amount <= 0.0 {
+            return Err(format!("sized to nothing ({})", how));
+        }
+        Ok((amount, how))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(mode: TriggerMode) -> TriggerRule {
+        TriggerRule { mode, cooldown: Duration::ZERO, every_n: 1, max_buys: 1 }
+    }
+
+    #[test]
+    fn first_mode_buys_once() {
+        let mut state = TriggerState::default();
+        assert!(state.evaluate(&rule(TriggerMode::First), "sig1").is_ok());
+        assert!(state.evaluate(&rule(TriggerMode::First), "sig2").is_err());
+        assert_eq!(state.buys, 1);
+    }
+
+    #[test]
+    fn same_claim_transaction_never_fires_twice() {
+        let mut state = TriggerState::default();
+        let every = rule(TriggerMode::Every);
+        assert!(state.evaluate(&every, "sig1").is_ok());
+        assert!(state.evaluate(&every, "sig1").unwrap_err().contains("already fired"));
+        assert_eq!(state.claims_seen, 1);
+        assert!(state.evaluate(&every, "sig2").is_ok());
+    }
+
+    #[test]
+    fn every_nth_fires_on_multiples_of_n() {
+        let mut state = TriggerState::default();
+        let every_third = TriggerRule { every_n: 3, ..rule(TriggerMode::EveryNth) };
+        let fired: Vec<bool> = (1..=7)
+            .map(|i| state.evaluate(&every_third, &format!("sig{}", i)).is_ok())
+            .collect();
+        assert_eq!(fired, [false, false, true, false, false, true, false]);
+    }
+
+    #[test]
+    fn every_nth_of_zero_fires_every_claim() {
+        let mut state = TriggerState::default();
+        let zero = TriggerRule { every_n: 0, ..rule(TriggerMode::EveryNth) };
+        assert!(state.evaluate(&zero, "sig1").is_ok());
+        assert!(state.evaluate(&zero, "sig2").is_ok());
+    }
+
+    #[test]
+    fn max_buys_stops_at_the_limit() {
+        let mut state = TriggerState::default();
+        let two = TriggerRule { max_buys: 2, ..rule(TriggerMode::MaxBuys) };
+        assert!(state.evaluate(&two, "sig1").is_ok());
+        assert!(state.evaluate(&two, "sig2").is_ok());
+        assert!(state.evaluate(&two, "sig3").unwrap_err().contains("max of 2"));
+    }
+
+    #[test]
+    fn cooldown_blocks_repeat_buys() {
+        let mut state = TriggerState::default();
+        let cooled = TriggerRule { cooldown: Duration::from_secs(60), ..rule(TriggerMode::Every) };
+        assert!(state.evaluate(&cooled, "sig1").is_ok());
+        assert!(state.evaluate(&cooled, "sig2").unwrap_err().contains("cooldown"));
+        assert_eq!(state.buys, 1);
+    }
+
+    #[test]
+    fn history_restores_buys() {
+        let mut sniped = TriggerState::from_history(&[], true);
+        assert!(sniped.evaluate(&rule(TriggerMode::First), "sig1").is_err());
+
+        let two = TriggerRule { max_buys: 2, ..rule(TriggerMode::MaxBuys) };
+        let mut state = TriggerState::from_history(&[Duration::from_secs(600), Duration::from_secs(60)], false);
+        assert_eq!(state.buys, 2);
+        assert!(state.evaluate(&two, "sig1").is_err());
+
+        let mut fresh = TriggerState::from_history(&[], false);
+        assert!(fresh.evaluate(&rule(TriggerMode::First), "sig1").is_ok());
+    }
+
+    const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+    fn tiers() -> BuySizing {
+        // Deliberately unsorted; `new` sorts by min_claim
+        BuySizing::new(BuyMode::Tiered, 0.0, 0.0, &[
+            BuyTier { min_claim: 10.0, amount: 0.5 },
+            BuyTier { min_claim: 1.0, amount: 0.1 },
+        ])
+    }
+
+    #[test]
+    fn fixed_ignores_balance_and_claim() {
+        let fixed = BuySizing::new(BuyMode::Fixed, 0.2, 50.0, &[]);
+        assert_eq!(fixed.amount(None, None, USDC_MINT).unwrap().0, 0.2);
+    }
+
+    #[test]
+    fn balance_percent_needs_a_known_balance() {
+        let half = BuySizing::new(BuyMode::BalancePercent, 0.0, 50.0, &[]);
+        assert_eq!(half.amount(Some(4.0), None, WSOL_MINT).unwrap().0, 2.0);
+        assert!(half.amount(None, Some(1.0), WSOL_MINT).is_err());
+    }
+
+    #[test]
+    fn claim_percent_sizes_off_the_claimed_sol() {
+        let tenth = BuySizing::new(BuyMode::ClaimPercent, 0.0, 10.0, &[]);
+        let (amount, how) = tenth.amount(None, Some(3.0), WSOL_MINT).unwrap();
+        assert!((amount - 0.3).abs() < 1e-9);
+        assert!(how.contains("claimed"));
+        assert!(tenth.amount(None, None, WSOL_MINT).is_err());
+    }
+
+    #[test]
+    fn claim_based_sizing_rejects_non_sol_claims() {
+        let tenth = BuySizing::new(BuyMode::ClaimPercent, 0.0, 10.0, &[]);
+        assert!(tenth.amount(None, Some(1000.0), USDC_MINT).unwrap_err().contains("not SOL"));
+        assert!(tiers().amount(None, Some(1000.0), USDC_MINT).unwrap_err().contains("not SOL"));
+    }
+
+    #[test]
+    fn tiered_picks_the_highest_tier_reached() {
+        let tiers = tiers();
+        assert!(tiers.amount(None, Some(0.5), WSOL_MINT).unwrap_err().contains("below the lowest tier"));
+        assert_eq!(tiers.amount(None, Some(1.0), WSOL_MINT).unwrap().0, 0.1);
+        assert_eq!(tiers.amount(None, Some(9.99), WSOL_MINT).unwrap().0, 0.1);
+        assert_eq!(tiers.amount(None, Some(10.0), WSOL_MINT).unwrap().0, 0.5);
+        assert_eq!(tiers.amount(None, Some(500.0), WSOL_MINT).unwrap().0, 0.5);
+    }
+
+    #[test]
+    fn zero_amount_is_a_skip() {
+        let empty_wallet = BuySizing::new(BuyMode::BalancePercent, 0.0, 50.0, &[]);
+        assert!(empty_wallet.amount(Some(0.0), None, WSOL_MINT).unwrap_err().contains("sized to nothing"));
+        assert!(BuySizing::new(BuyMode::Fixed, 0.0, 0.0, &[]).amount(None, None, WSOL_MINT).is_err());
+    }
+}
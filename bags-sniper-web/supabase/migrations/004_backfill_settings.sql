@@ -0,0 +1,7 @@
+-- Max age for claims recovered by the RPC backfill after a stream outage
+-- Run this in Supabase SQL Editor
+
+ALTER TABLE user_settings 
+ADD COLUMN IF NOT EXISTS max_backfill_age_secs INTEGER DEFAULT 30;
+
+COMMENT ON COLUMN user_settings.max_backfill_age_secs IS 'Skip backfilled claims older than this many seconds. NULL acts on backfilled claims of any age.';
@@ -0,0 +1,8 @@
+-- Per-watchlist-item trigger policy
+-- Run this in Supabase SQL Editor
+
+ALTER TABLE watchlist 
+ADD COLUMN IF NOT EXISTS trigger_policy TEXT DEFAULT 'any'
+CHECK (trigger_policy IN ('any', 'creator', 'protocol'));
+
+COMMENT ON COLUMN watchlist.trigger_policy IS 'Which claims fire the buy: any claim, creator-signed claims only, or protocol/partner distributions only.';
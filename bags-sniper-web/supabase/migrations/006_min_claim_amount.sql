@@ -0,0 +1,7 @@
+-- Minimum claim size before a watchlist item fires
+-- Run this in Supabase SQL Editor
+
+ALTER TABLE watchlist 
+ADD COLUMN IF NOT EXISTS min_claim_amount DECIMAL(18, 9) DEFAULT 0;
+
+COMMENT ON COLUMN watchlist.min_claim_amount IS 'Only buy when the claimer received at least this much SOL (or quote token). 0 fires on any claim.';
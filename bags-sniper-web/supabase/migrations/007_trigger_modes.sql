@@ -0,0 +1,17 @@
+-- Trigger modes: first claim / every claim / every Nth claim / up to K buys
+-- Run this in Supabase SQL Editor
+
+ALTER TABLE watchlist 
+ADD COLUMN IF NOT EXISTS trigger_mode TEXT DEFAULT 'first'
+CHECK (trigger_mode IN ('first', 'every', 'every_nth', 'max_buys'));
+
+ALTER TABLE watchlist 
+ADD COLUMN IF NOT EXISTS cooldown_secs INTEGER DEFAULT 0;
+
+ALTER TABLE watchlist 
+ADD COLUMN IF NOT EXISTS trigger_every_n INTEGER DEFAULT 1;
+
+ALTER TABLE watchlist 
+ADD COLUMN IF NOT EXISTS max_buys INTEGER DEFAULT 1;
+
+COMMENT ON COLUMN watchlist.trigger_mode IS 'first = buy once; every = every claim after cooldown_secs; every_nth = every trigger_every_n claims; max_buys = until max_buys buys.';
@@ -0,0 +1,9 @@
+-- Buy history lookup used to restore snipe idempotency on startup
+-- Run this in Supabase SQL Editor
+
+-- Buys are inserted as PENDING before the transaction is sent and
+-- updated to SUCCESS/FAILED afterwards. PENDING rows left behind by a
+-- crash are treated as bought so the sniper never buys twice.
+CREATE INDEX IF NOT EXISTS idx_trade_logs_buys
+ON trade_logs(wallet_address, mint_address)
+WHERE action = 'BUY' AND status IN ('SUCCESS', 'PENDING');
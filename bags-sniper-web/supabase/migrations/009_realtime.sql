@@ -0,0 +1,13 @@
+-- Stream sniper tables to the Rust core over Supabase Realtime
+-- Run this in Supabase SQL Editor
+
+-- Publish row changes
+ALTER PUBLICATION supabase_realtime ADD TABLE watchlist;
+ALTER PUBLICATION supabase_realtime ADD TABLE user_settings;
+ALTER PUBLICATION supabase_realtime ADD TABLE sniper_status;
+
+-- Include the full old row on UPDATE/DELETE so the core knows which
+-- wallet and mint a deleted row belonged to
+ALTER TABLE watchlist REPLICA IDENTITY FULL;
+ALTER TABLE user_settings REPLICA IDENTITY FULL;
+ALTER TABLE sniper_status REPLICA IDENTITY FULL;
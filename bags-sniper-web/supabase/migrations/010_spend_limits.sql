@@ -0,0 +1,11 @@
+-- Per-user spend limits enforced before every buy
+-- Run this in Supabase SQL Editor
+
+-- max_buy_per_token already exists (001_full_schema.sql)
+ALTER TABLE user_settings 
+ADD COLUMN IF NOT EXISTS max_daily_spend DECIMAL(18, 9),
+ADD COLUMN IF NOT EXISTS max_total_spend DECIMAL(18, 9);
+
+COMMENT ON COLUMN user_settings.max_buy_per_token IS 'Max SOL spent on a single token across all buys. NULL = no cap.';
+COMMENT ON COLUMN user_settings.max_daily_spend IS 'Max SOL spent in any rolling 24h window. NULL = no cap.';
+COMMENT ON COLUMN user_settings.max_total_spend IS 'Max SOL spent over the lifetime of the account. NULL = no cap.';
@@ -0,0 +1,14 @@
+-- Buy sizing strategies per watchlist item
+-- Run this in Supabase SQL Editor
+
+ALTER TABLE watchlist 
+ADD COLUMN IF NOT EXISTS buy_mode TEXT DEFAULT 'fixed' CHECK (buy_mode IN ('fixed', 'balance_percent', 'claim_percent', 'tiered')),
+ADD COLUMN IF NOT EXISTS buy_percent DECIMAL(6, 2) DEFAULT 0,
+ADD COLUMN IF NOT EXISTS buy_tiers JSONB DEFAULT '[]'::jsonb;
+
+COMMENT ON COLUMN watchlist.buy_mode IS 'fixed = buy_amount SOL, balance_percent = buy_percent of free wallet SOL, claim_percent = buy_percent of the claimed SOL, tiered = buy_tiers keyed on claim size. Claim-based modes skip claims not paid in SOL (WSOL quote).';
+COMMENT ON COLUMN watchlist.buy_tiers IS 'Tiered mode: [{"min_claim": 1.0, "amount": 0.1}, {"min_claim": 10.0, "amount": 0.5}]. min_claim is in SOL; the highest tier at or below the claimed SOL is used. Claims in other quote tokens (e.g. USDC) are skipped.';
+
+-- How each buy's amount_sol was sized
+ALTER TABLE trade_logs 
+ADD COLUMN IF NOT EXISTS sizing TEXT;
@@ -0,0 +1,27 @@
+-- Lifecycle of each (wallet, mint) snipe, written by the Rust core
+-- Run this in Supabase SQL Editor
+
+CREATE TABLE IF NOT EXISTS snipes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    wallet_address TEXT NOT NULL,
+    mint_address TEXT NOT NULL,
+    state TEXT NOT NULL DEFAULT 'watching' CHECK (state IN ('watching', 'claim_detected', 'tx_sent', 'confirmed', 'failed')),
+    claim_signature TEXT,
+    buy_signature TEXT,
+    error_message TEXT,
+    watching_at TIMESTAMP WITH TIME ZONE,
+    claim_detected_at TIMESTAMP WITH TIME ZONE,
+    tx_sent_at TIMESTAMP WITH TIME ZONE,
+    confirmed_at TIMESTAMP WITH TIME ZONE,
+    failed_at TIMESTAMP WITH TIME ZONE,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    UNIQUE(wallet_address, mint_address)
+);
+
+CREATE INDEX IF NOT EXISTS idx_snipes_wallet ON snipes(wallet_address);
+
+ALTER TABLE snipes ENABLE ROW LEVEL SECURITY;
+CREATE POLICY "Allow all on snipes" ON snipes FOR ALL USING (true);
+
+-- Let the dashboard follow snipes live
+ALTER PUBLICATION supabase_realtime ADD TABLE snipes;
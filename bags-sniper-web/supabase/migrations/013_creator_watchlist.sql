@@ -0,0 +1,30 @@
+-- Watch creators (dev wallets) instead of individual mints
+-- Any Bags claim_user signed by a watched creator buys the claimed token
+-- Run this in Supabase SQL Editor
+
+CREATE TABLE IF NOT EXISTS creator_watchlist (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    wallet_address TEXT NOT NULL,
+    creator_address TEXT NOT NULL,
+    buy_amount NUMERIC NOT NULL DEFAULT 0.1,
+    is_active BOOLEAN DEFAULT TRUE,
+    min_claim_amount NUMERIC DEFAULT 0,
+    trigger_mode TEXT DEFAULT 'first' CHECK (trigger_mode IN ('first', 'every', 'every_nth', 'max_buys')),
+    cooldown_secs INTEGER DEFAULT 0,
+    trigger_every_n INTEGER DEFAULT 1,
+    max_buys INTEGER DEFAULT 1,
+    buy_mode TEXT DEFAULT 'fixed' CHECK (buy_mode IN ('fixed', 'balance_percent', 'claim_percent', 'tiered')),
+    buy_percent NUMERIC DEFAULT 0,
+    buy_tiers JSONB DEFAULT '[]'::jsonb,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    UNIQUE(wallet_address, creator_address)
+);
+
+CREATE INDEX IF NOT EXISTS idx_creator_watchlist_wallet ON creator_watchlist(wallet_address);
+
+ALTER TABLE creator_watchlist ENABLE ROW LEVEL SECURITY;
+CREATE POLICY "Allow all on creator_watchlist" ON creator_watchlist FOR ALL USING (true);
+
+-- Picked up live by the Rust core
+ALTER PUBLICATION supabase_realtime ADD TABLE creator_watchlist;
+ALTER TABLE creator_watchlist REPLICA IDENTITY FULL;
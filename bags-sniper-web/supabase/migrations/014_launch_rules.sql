@@ -0,0 +1,42 @@
+-- Auto-watch newly launched Bags tokens by rule
+-- Every set criterion must match; the buy settings are copied onto the watchlist entry
+-- Run this in Supabase SQL Editor
+
+CREATE TABLE IF NOT EXISTS launch_rules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    wallet_address TEXT NOT NULL,
+    is_active BOOLEAN DEFAULT TRUE,
+    creator_allowlist TEXT[] DEFAULT '{}',
+    name_pattern TEXT,
+    symbol_pattern TEXT,
+    min_initial_buy NUMERIC,
+    max_initial_buy NUMERIC,
+    buy_amount NUMERIC NOT NULL DEFAULT 0.1,
+    trigger_policy TEXT DEFAULT 'any' CHECK (trigger_policy IN ('any', 'creator', 'protocol')),
+    min_claim_amount NUMERIC DEFAULT 0,
+    trigger_mode TEXT DEFAULT 'first' CHECK (trigger_mode IN ('first', 'every', 'every_nth', 'max_buys')),
+    cooldown_secs INTEGER DEFAULT 0,
+    trigger_every_n INTEGER DEFAULT 1,
+    max_buys INTEGER DEFAULT 1,
+    buy_mode TEXT DEFAULT 'fixed' CHECK (buy_mode IN ('fixed', 'balance_percent', 'claim_percent', 'tiered')),
+    buy_percent NUMERIC DEFAULT 0,
+    buy_tiers JSONB DEFAULT '[]'::jsonb,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_launch_rules_wallet ON launch_rules(wallet_address);
+
+ALTER TABLE launch_rules ENABLE ROW LEVEL SECURITY;
+CREATE POLICY "Allow all on launch_rules" ON launch_rules FOR ALL USING (true);
+
+-- Where each watchlist entry came from
+ALTER TABLE watchlist
+ADD COLUMN IF NOT EXISTS source TEXT DEFAULT 'manual' CHECK (source IN ('manual', 'launch_rule')),
+ADD COLUMN IF NOT EXISTS launch_rule_id UUID REFERENCES launch_rules(id) ON DELETE SET NULL,
+ADD COLUMN IF NOT EXISTS launch_signature TEXT;
+
+COMMENT ON COLUMN watchlist.source IS 'manual = added by the user; launch_rule = added by launch_rule_id when launch_signature was seen.';
+
+-- Picked up live by the Rust core
+ALTER PUBLICATION supabase_realtime ADD TABLE launch_rules;
+ALTER TABLE launch_rules REPLICA IDENTITY FULL;
@@ -0,0 +1,16 @@
+-- Per-user pre-trade risk filters
+-- Each enabled check skips buys of tokens that fail it
+-- Run this in Supabase SQL Editor
+
+ALTER TABLE user_settings 
+ADD COLUMN IF NOT EXISTS risk_block_mint_authority BOOLEAN DEFAULT FALSE,
+ADD COLUMN IF NOT EXISTS risk_block_freeze_authority BOOLEAN DEFAULT FALSE,
+ADD COLUMN IF NOT EXISTS risk_block_transfer_fee BOOLEAN DEFAULT FALSE,
+ADD COLUMN IF NOT EXISTS risk_block_transfer_hook BOOLEAN DEFAULT FALSE,
+ADD COLUMN IF NOT EXISTS risk_max_top_holders_pct DECIMAL(5, 2);
+
+COMMENT ON COLUMN user_settings.risk_block_mint_authority IS 'Skip tokens whose mint authority is still set.';
+COMMENT ON COLUMN user_settings.risk_block_freeze_authority IS 'Skip tokens with a freeze authority.';
+COMMENT ON COLUMN user_settings.risk_block_transfer_fee IS 'Skip Token-2022 tokens with a transfer fee.';
+COMMENT ON COLUMN user_settings.risk_block_transfer_hook IS 'Skip Token-2022 tokens with a transfer hook.';
+COMMENT ON COLUMN user_settings.risk_max_top_holders_pct IS 'Skip tokens whose top 10 wallets hold more than this percent of supply (pool vaults excluded). NULL = no check.';
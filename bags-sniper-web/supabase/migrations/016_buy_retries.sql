@@ -0,0 +1,12 @@
+-- Retry pipeline: one trade_logs row per buy attempt, per-user retry deadline
+-- Run this in Supabase SQL Editor
+
+ALTER TABLE trade_logs 
+ADD COLUMN IF NOT EXISTS attempt INTEGER DEFAULT 1;
+
+COMMENT ON COLUMN trade_logs.attempt IS 'Attempt number within one buy. Failed attempts keep their signature and classified error ([kind] message).';
+
+ALTER TABLE user_settings 
+ADD COLUMN IF NOT EXISTS buy_deadline_secs INTEGER DEFAULT 20;
+
+COMMENT ON COLUMN user_settings.buy_deadline_secs IS 'No new buy attempt (resend, re-sign or re-quote) starts after this many seconds.';
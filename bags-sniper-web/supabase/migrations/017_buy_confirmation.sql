@@ -0,0 +1,10 @@
+-- Non-blocking buys: commitment of each snipe's current buy, reported by the confirmation tracker
+-- Run this in Supabase SQL Editor
+
+ALTER TABLE snipes 
+ADD COLUMN IF NOT EXISTS buy_commitment TEXT CHECK (buy_commitment IN ('processed', 'confirmed', 'finalized', 'failed', 'expired')),
+ADD COLUMN IF NOT EXISTS processed_at TIMESTAMP WITH TIME ZONE,
+ADD COLUMN IF NOT EXISTS finalized_at TIMESTAMP WITH TIME ZONE,
+ADD COLUMN IF NOT EXISTS expired_at TIMESTAMP WITH TIME ZONE;
+
+COMMENT ON COLUMN snipes.buy_commitment IS 'Latest commitment of buy_signature. expired means its blockhash ran out before it landed; a retry may follow.';
@@ -0,0 +1,35 @@
+-- Stop streaming users (it holds encrypted_private_key) over Realtime.
+-- Key changes are signalled through sniper_status.key_version instead;
+-- the core re-fetches the key over REST.
+-- Run this in Supabase SQL Editor
+
+DO $$
+BEGIN
+    IF EXISTS (
+        SELECT 1 FROM pg_publication_tables
+        WHERE pubname = 'supabase_realtime' AND schemaname = 'public' AND tablename = 'users'
+    ) THEN
+        ALTER PUBLICATION supabase_realtime DROP TABLE users;
+    END IF;
+END $$;
+
+ALTER TABLE users REPLICA IDENTITY DEFAULT;
+
+ALTER TABLE sniper_status 
+ADD COLUMN IF NOT EXISTS key_version INTEGER NOT NULL DEFAULT 0;
+
+COMMENT ON COLUMN sniper_status.key_version IS 'Bumped whenever users.encrypted_private_key changes, so the core reloads the key.';
+
+CREATE OR REPLACE FUNCTION bump_key_version() RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE sniper_status
+    SET key_version = key_version + 1
+    WHERE wallet_address = NEW.wallet_address;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql SECURITY DEFINER;
+
+DROP TRIGGER IF EXISTS users_key_version ON users;
+CREATE TRIGGER users_key_version
+AFTER INSERT OR UPDATE OF encrypted_private_key ON users
+FOR EACH ROW EXECUTE FUNCTION bump_key_version();
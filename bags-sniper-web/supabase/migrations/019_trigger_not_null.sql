@@ -0,0 +1,47 @@
+-- Trigger settings are required: the core reads them as plain values,
+-- so a single NULL would fail loading the user's whole watchlist.
+-- Existing NULLs are reset to the column default first.
+-- Run this in Supabase SQL Editor
+
+UPDATE watchlist SET trigger_policy = 'any' WHERE trigger_policy IS NULL;
+UPDATE watchlist SET min_claim_amount = 0 WHERE min_claim_amount IS NULL;
+UPDATE watchlist SET trigger_mode = 'first' WHERE trigger_mode IS NULL;
+UPDATE watchlist SET cooldown_secs = 0 WHERE cooldown_secs IS NULL;
+UPDATE watchlist SET trigger_every_n = 1 WHERE trigger_every_n IS NULL;
+UPDATE watchlist SET max_buys = 1 WHERE max_buys IS NULL;
+
+ALTER TABLE watchlist
+ALTER COLUMN trigger_policy SET NOT NULL,
+ALTER COLUMN min_claim_amount SET NOT NULL,
+ALTER COLUMN trigger_mode SET NOT NULL,
+ALTER COLUMN cooldown_secs SET NOT NULL,
+ALTER COLUMN trigger_every_n SET NOT NULL,
+ALTER COLUMN max_buys SET NOT NULL;
+
+UPDATE creator_watchlist SET min_claim_amount = 0 WHERE min_claim_amount IS NULL;
+UPDATE creator_watchlist SET trigger_mode = 'first' WHERE trigger_mode IS NULL;
+UPDATE creator_watchlist SET cooldown_secs = 0 WHERE cooldown_secs IS NULL;
+UPDATE creator_watchlist SET trigger_every_n = 1 WHERE trigger_every_n IS NULL;
+UPDATE creator_watchlist SET max_buys = 1 WHERE max_buys IS NULL;
+
+ALTER TABLE creator_watchlist
+ALTER COLUMN min_claim_amount SET NOT NULL,
+ALTER COLUMN trigger_mode SET NOT NULL,
+ALTER COLUMN cooldown_secs SET NOT NULL,
+ALTER COLUMN trigger_every_n SET NOT NULL,
+ALTER COLUMN max_buys SET NOT NULL;
+
+UPDATE launch_rules SET trigger_policy = 'any' WHERE trigger_policy IS NULL;
+UPDATE launch_rules SET min_claim_amount = 0 WHERE min_claim_amount IS NULL;
+UPDATE launch_rules SET trigger_mode = 'first' WHERE trigger_mode IS NULL;
+UPDATE launch_rules SET cooldown_secs = 0 WHERE cooldown_secs IS NULL;
+UPDATE launch_rules SET trigger_every_n = 1 WHERE trigger_every_n IS NULL;
+UPDATE launch_rules SET max_buys = 1 WHERE max_buys IS NULL;
+
+ALTER TABLE launch_rules
+ALTER COLUMN trigger_policy SET NOT NULL,
+ALTER COLUMN min_claim_amount SET NOT NULL,
+ALTER COLUMN trigger_mode SET NOT NULL,
+ALTER COLUMN cooldown_secs SET NOT NULL,
+ALTER COLUMN trigger_every_n SET NOT NULL,
+ALTER COLUMN max_buys SET NOT NULL;
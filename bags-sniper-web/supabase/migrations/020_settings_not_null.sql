@@ -0,0 +1,44 @@
+-- Buy deadline, risk checks and buy sizing are required: the core reads
+-- them as plain values, so a single NULL would fail loading the user's
+-- settings or watchlist. Existing NULLs are reset to the column default first.
+-- Run this in Supabase SQL Editor
+
+UPDATE user_settings SET buy_deadline_secs = 20 WHERE buy_deadline_secs IS NULL;
+UPDATE user_settings SET risk_block_mint_authority = FALSE WHERE risk_block_mint_authority IS NULL;
+UPDATE user_settings SET risk_block_freeze_authority = FALSE WHERE risk_block_freeze_authority IS NULL;
+UPDATE user_settings SET risk_block_transfer_fee = FALSE WHERE risk_block_transfer_fee IS NULL;
+UPDATE user_settings SET risk_block_transfer_hook = FALSE WHERE risk_block_transfer_hook IS NULL;
+
+ALTER TABLE user_settings
+ALTER COLUMN buy_deadline_secs SET NOT NULL,
+ALTER COLUMN risk_block_mint_authority SET NOT NULL,
+ALTER COLUMN risk_block_freeze_authority SET NOT NULL,
+ALTER COLUMN risk_block_transfer_fee SET NOT NULL,
+ALTER COLUMN risk_block_transfer_hook SET NOT NULL;
+
+UPDATE watchlist SET buy_mode = 'fixed' WHERE buy_mode IS NULL;
+UPDATE watchlist SET buy_percent = 0 WHERE buy_percent IS NULL;
+UPDATE watchlist SET buy_tiers = '[]'::jsonb WHERE buy_tiers IS NULL;
+
+ALTER TABLE watchlist
+ALTER COLUMN buy_mode SET NOT NULL,
+ALTER COLUMN buy_percent SET NOT NULL,
+ALTER COLUMN buy_tiers SET NOT NULL;
+
+UPDATE creator_watchlist SET buy_mode = 'fixed' WHERE buy_mode IS NULL;
+UPDATE creator_watchlist SET buy_percent = 0 WHERE buy_percent IS NULL;
+UPDATE creator_watchlist SET buy_tiers = '[]'::jsonb WHERE buy_tiers IS NULL;
+
+ALTER TABLE creator_watchlist
+ALTER COLUMN buy_mode SET NOT NULL,
+ALTER COLUMN buy_percent SET NOT NULL,
+ALTER COLUMN buy_tiers SET NOT NULL;
+
+UPDATE launch_rules SET buy_mode = 'fixed' WHERE buy_mode IS NULL;
+UPDATE launch_rules SET buy_percent = 0 WHERE buy_percent IS NULL;
+UPDATE launch_rules SET buy_tiers = '[]'::jsonb WHERE buy_tiers IS NULL;
+
+ALTER TABLE launch_rules
+ALTER COLUMN buy_mode SET NOT NULL,
+ALTER COLUMN buy_percent SET NOT NULL,
+ALTER COLUMN buy_tiers SET NOT NULL;